@@ -4,11 +4,15 @@ use crate::{errors::BridgeError, events::BurnEvent};
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Burn};
 
+/// Maximum length of a Zcash address, unified addresses with all
+/// receivers are the longest ones
+pub const MAX_ZEC_RECIPIENT_LEN: usize = 256;
+
 /// Burn sZEC to bridge back to Zcash (public action)
 pub fn burn(ctx: Context<crate::BurnZec>, amount: u64, zec_recipient: String) -> Result<()> {
     require!(amount > 0, BridgeError::InvalidAmount);
     require!(
-        !zec_recipient.is_empty() && zec_recipient.len() == 110,
+        is_zcash_address(&zec_recipient),
         BridgeError::InvalidZcashAddress
     );
 
@@ -32,3 +36,31 @@ pub fn burn(ctx: Context<crate::BurnZec>, amount: u64, zec_recipient: String) ->
 
     Ok(())
 }
+
+/// Check the format of a Zcash address by its prefix and length
+///
+/// Accepts unified, sapling, TEX and transparent addresses of mainnet and
+/// testnet, the checksums are verified by the validators off-chain.
+fn is_zcash_address(address: &str) -> bool {
+    if !address.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return false;
+    }
+
+    let len = address.len();
+    if address.starts_with("u1") || address.starts_with("utest1") {
+        (106..=MAX_ZEC_RECIPIENT_LEN).contains(&len)
+    } else if address.starts_with("zs1") {
+        len == 78
+    } else if address.starts_with("ztestsapling1") {
+        len == 88
+    } else if address.starts_with("tex1") {
+        len == 42
+    } else if address.starts_with("textest1") {
+        len == 46
+    } else {
+        ["t1", "t3", "tm", "t2"]
+            .iter()
+            .any(|prefix| address.starts_with(prefix))
+            && len == 35
+    }
+}
@@ -2,6 +2,7 @@
 
 use super::Database;
 use anyhow::Result;
+use rusqlite::Connection;
 
 impl Database {
     /// Initialize the database schema
@@ -12,6 +13,7 @@ impl Database {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS blocks (
                 slot INTEGER PRIMARY KEY,
+                version INTEGER NOT NULL DEFAULT 0,
                 hash BLOB NOT NULL,
                 parent BLOB NOT NULL,
                 state BLOB NOT NULL,
@@ -31,6 +33,7 @@ impl Database {
                 coin TEXT NOT NULL,
                 recipient BLOB NOT NULL,
                 amount INTEGER NOT NULL,
+                fee INTEGER NOT NULL DEFAULT 0,
                 source TEXT NOT NULL,
                 target TEXT NOT NULL,
                 slot INTEGER NOT NULL,
@@ -46,6 +49,7 @@ impl Database {
                 txid BLOB PRIMARY KEY,
                 anchor BLOB NOT NULL,
                 coin TEXT NOT NULL,
+                amount INTEGER NOT NULL DEFAULT 0,
                 source TEXT NOT NULL,
                 target TEXT NOT NULL,
                 slot INTEGER NOT NULL,
@@ -54,6 +58,13 @@ impl Database {
             [],
         )?;
 
+        // Add the fee columns to databases created before the fee schedule
+        add_column(&conn, "bridges", "fee", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column(&conn, "receipts", "amount", "INTEGER NOT NULL DEFAULT 0")?;
+
+        // Add the header version column, the legacy blocks are version 0
+        add_column(&conn, "blocks", "version", "INTEGER NOT NULL DEFAULT 0")?;
+
         // Create indexes for efficient querying
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_bridges_slot ON bridges(slot)",
@@ -104,3 +115,20 @@ impl Database {
         Ok(())
     }
 }
+
+/// Add a column to the table if it does not exist
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{table}') WHERE name = ?1"),
+        [column],
+        |row| row.get(0),
+    )?;
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+            [],
+        )?;
+    }
+    Ok(())
+}
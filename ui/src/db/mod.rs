@@ -25,6 +25,7 @@ pub struct BridgeTransactionResult {
     pub txid: String,
     pub coin: String,
     pub amount: u64,
+    pub fee: u64,
     pub recipient: String,
     pub source: String,
     pub target: String,
@@ -37,6 +38,7 @@ pub struct BridgeTransactionResult {
 pub struct ReceiptInfo {
     pub anchor: String,
     pub coin: String,
+    pub amount: u64,
     pub txid: String,
     pub source: String,
     pub target: String,
@@ -13,12 +13,12 @@ impl Database {
 
         // Query the bridge transaction
         let mut stmt = conn.prepare(
-            "SELECT coin, recipient, amount, source, target, slot
+            "SELECT coin, recipient, amount, fee, source, target, slot
              FROM bridges
              WHERE txid = ?1",
         )?;
 
-        let result: Option<(String, Vec<u8>, u64, String, String, u32)> = stmt
+        let result: Option<(String, Vec<u8>, u64, u64, String, String, u32)> = stmt
             .query_row(params![txid], |row| {
                 Ok((
                     row.get(0)?,
@@ -27,17 +27,18 @@ impl Database {
                     row.get(3)?,
                     row.get(4)?,
                     row.get(5)?,
+                    row.get(6)?,
                 ))
             })
             .optional()?;
 
-        let Some((coin, recipient, amount, source, target, slot)) = result else {
+        let Some((coin, recipient, amount, fee, source, target, slot)) = result else {
             return Ok(None);
         };
 
         // Query for receipt where anchor matches the txid
         let mut receipt_stmt = conn.prepare(
-            "SELECT txid, anchor, coin, source, target, slot
+            "SELECT txid, anchor, coin, amount, source, target, slot
              FROM receipts
              WHERE anchor = ?1",
         )?;
@@ -47,12 +48,14 @@ impl Database {
                 let receipt_txid: Vec<u8> = row.get(0)?;
                 let receipt_anchor: Vec<u8> = row.get(1)?;
                 let receipt_coin: String = row.get(2)?;
-                let receipt_source: String = row.get(3)?;
-                let receipt_target: String = row.get(4)?;
-                let receipt_slot: u32 = row.get(5)?;
+                let receipt_amount: u64 = row.get(3)?;
+                let receipt_source: String = row.get(4)?;
+                let receipt_target: String = row.get(5)?;
+                let receipt_slot: u32 = row.get(6)?;
                 Ok(ReceiptInfo {
                     anchor: encode_txid(&receipt_anchor),
                     coin: receipt_coin,
+                    amount: receipt_amount,
                     txid: encode_txid(&receipt_txid),
                     source: receipt_source,
                     target: receipt_target,
@@ -65,6 +68,7 @@ impl Database {
             txid: encode_txid(txid),
             coin,
             amount,
+            fee,
             recipient: encode_recipient(&recipient),
             source,
             target,
@@ -104,13 +108,13 @@ impl Database {
     /// Get a block by slot
     pub fn get_block_by_slot(&self, slot: u32) -> Result<Option<Block>> {
         let conn = self.conn.lock().unwrap();
-        self.query_block(&conn, "SELECT slot, hash, parent, state, accumulator, extrinsic, votes FROM blocks WHERE slot = ?1", params![slot])
+        self.query_block(&conn, "SELECT slot, hash, parent, state, accumulator, extrinsic, votes, version FROM blocks WHERE slot = ?1", params![slot])
     }
 
     /// Get a block by hash
     pub fn get_block_by_hash(&self, hash: &[u8]) -> Result<Option<Block>> {
         let conn = self.conn.lock().unwrap();
-        self.query_block(&conn, "SELECT slot, hash, parent, state, accumulator, extrinsic, votes FROM blocks WHERE hash = ?1", params![hash])
+        self.query_block(&conn, "SELECT slot, hash, parent, state, accumulator, extrinsic, votes, version FROM blocks WHERE hash = ?1", params![hash])
     }
 
     /// Internal helper to query a block
@@ -121,7 +125,16 @@ impl Database {
         params: impl rusqlite::Params,
     ) -> Result<Option<Block>> {
         let mut stmt = conn.prepare(sql)?;
-        let result: Option<(u32, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>)> = stmt
+        let result: Option<(
+            u32,
+            Vec<u8>,
+            Vec<u8>,
+            Vec<u8>,
+            Vec<u8>,
+            Vec<u8>,
+            Vec<u8>,
+            u8,
+        )> = stmt
             .query_row(params, |row| {
                 Ok((
                     row.get(0)?,
@@ -131,40 +144,46 @@ impl Database {
                     row.get(4)?,
                     row.get(5)?,
                     row.get(6)?,
+                    row.get(7)?,
                 ))
             })
             .optional()?;
 
-        let Some((slot, _hash, parent, state, accumulator, extrinsic, votes_bytes)) = result else {
+        let Some((slot, _hash, parent, state, accumulator, extrinsic, votes_bytes, version)) =
+            result
+        else {
             return Ok(None);
         };
 
-        // Deserialize votes
-        let votes: std::collections::BTreeMap<[u8; 32], Vec<u8>> =
-            postcard::from_bytes(&votes_bytes)?;
+        // Deserialize votes, the legacy rows are raw postcard
+        type Votes = std::collections::BTreeMap<[u8; 32], Vec<u8>>;
+        let votes = zcore::codec::decode::<Votes, Votes>(&votes_bytes)?;
 
         // Query bridge transactions for this block
         let mut bridge_stmt = conn.prepare(
-            "SELECT txid, coin, recipient, amount, source, target, bundle_hash FROM bridges WHERE slot = ?1",
+            "SELECT txid, coin, recipient, amount, fee, source, target, bundle_hash FROM bridges WHERE slot = ?1",
         )?;
-        let bridges: Vec<(Vec<u8>, String, Vec<u8>, u64, String, String, Vec<u8>)> = bridge_stmt
-            .query_map(params![slot], |row| {
-                Ok((
-                    row.get(0)?,
-                    row.get(1)?,
-                    row.get(2)?,
-                    row.get(3)?,
-                    row.get(4)?,
-                    row.get(5)?,
-                    row.get(6)?,
-                ))
-            })?
-            .collect::<Result<Vec<_>, _>>()?;
+        let bridges: Vec<(Vec<u8>, String, Vec<u8>, u64, u64, String, String, Vec<u8>)> =
+            bridge_stmt
+                .query_map(params![slot], |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                        row.get(6)?,
+                        row.get(7)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
 
         // Query receipts for this block
-        let mut receipt_stmt = conn
-            .prepare("SELECT txid, anchor, coin, source, target FROM receipts WHERE slot = ?1")?;
-        let receipts: Vec<(Vec<u8>, Vec<u8>, String, String, String)> = receipt_stmt
+        let mut receipt_stmt = conn.prepare(
+            "SELECT txid, anchor, coin, amount, source, target FROM receipts WHERE slot = ?1",
+        )?;
+        let receipts: Vec<(Vec<u8>, Vec<u8>, String, u64, String, String)> = receipt_stmt
             .query_map(params![slot], |row| {
                 Ok((
                     row.get(0)?,
@@ -172,6 +191,7 @@ impl Database {
                     row.get(2)?,
                     row.get(3)?,
                     row.get(4)?,
+                    row.get(5)?,
                 ))
             })?
             .collect::<Result<Vec<_>, _>>()?;
@@ -182,7 +202,7 @@ impl Database {
 
         // Group bridges by bundle_hash
         let mut bridge_map: BTreeMap<[u8; 32], BridgeBundle> = BTreeMap::new();
-        for (txid, coin, recipient, amount, source, target, bundle_hash) in bridges {
+        for (txid, coin, recipient, amount, fee, source, target, bundle_hash) in bridges {
             let bundle_key: [u8; 32] = bundle_hash
                 .try_into()
                 .map_err(|_| anyhow::anyhow!("Invalid bundle hash length"))?;
@@ -195,6 +215,7 @@ impl Database {
                 coin: parse_coin(&coin),
                 recipient,
                 amount,
+                fee,
                 source: parse_chain(&source),
                 target: parse_chain(&target),
                 txid,
@@ -204,9 +225,10 @@ impl Database {
         // Convert receipts
         let receipt_list: Vec<Receipt> = receipts
             .into_iter()
-            .map(|(txid, anchor, coin, source, target)| Receipt {
+            .map(|(txid, anchor, coin, amount, source, target)| Receipt {
                 anchor,
                 coin: parse_coin(&coin),
+                amount,
                 txid,
                 source: parse_chain(&source),
                 target: parse_chain(&target),
@@ -214,6 +236,7 @@ impl Database {
             .collect();
 
         let header = zcore::Header {
+            version,
             slot,
             parent: parent
                 .try_into()
@@ -304,8 +327,9 @@ impl Database {
         // Get paginated bridges with LEFT JOIN to receipts
         let offset = page * row;
         let mut stmt = conn.prepare(
-            "SELECT b.txid, b.coin, b.recipient, b.amount, b.source, b.target, b.slot,
+            "SELECT b.txid, b.coin, b.recipient, b.amount, b.fee, b.source, b.target, b.slot,
                     r.txid as receipt_txid, r.anchor as receipt_anchor, r.coin as receipt_coin,
+                    r.amount as receipt_amount,
                     r.source as receipt_source, r.target as receipt_target, r.slot as receipt_slot
              FROM bridges b
              LEFT JOIN receipts r ON r.anchor = b.txid
@@ -319,26 +343,30 @@ impl Database {
                 let coin: String = row.get(1)?;
                 let recipient: Vec<u8> = row.get(2)?;
                 let amount: u64 = row.get(3)?;
-                let source: String = row.get(4)?;
-                let target: String = row.get(5)?;
-                let slot: u32 = row.get(6)?;
-                let receipt_txid: Option<Vec<u8>> = row.get(7)?;
-                let receipt_anchor: Option<Vec<u8>> = row.get(8)?;
-                let receipt_coin: Option<String> = row.get(9)?;
-                let receipt_source: Option<String> = row.get(10)?;
-                let receipt_target: Option<String> = row.get(11)?;
-                let receipt_slot: Option<u32> = row.get(12)?;
+                let fee: u64 = row.get(4)?;
+                let source: String = row.get(5)?;
+                let target: String = row.get(6)?;
+                let slot: u32 = row.get(7)?;
+                let receipt_txid: Option<Vec<u8>> = row.get(8)?;
+                let receipt_anchor: Option<Vec<u8>> = row.get(9)?;
+                let receipt_coin: Option<String> = row.get(10)?;
+                let receipt_amount: Option<u64> = row.get(11)?;
+                let receipt_source: Option<String> = row.get(12)?;
+                let receipt_target: Option<String> = row.get(13)?;
+                let receipt_slot: Option<u32> = row.get(14)?;
                 Ok((
                     txid,
                     coin,
                     recipient,
                     amount,
+                    fee,
                     source,
                     target,
                     slot,
                     receipt_txid,
                     receipt_anchor,
                     receipt_coin,
+                    receipt_amount,
                     receipt_source,
                     receipt_target,
                     receipt_slot,
@@ -351,12 +379,14 @@ impl Database {
                     coin,
                     recipient,
                     amount,
+                    fee,
                     source,
                     target,
                     slot,
                     receipt_txid,
                     receipt_anchor,
                     receipt_coin,
+                    receipt_amount,
                     receipt_source,
                     receipt_target,
                     receipt_slot,
@@ -365,6 +395,7 @@ impl Database {
                         receipt_txid,
                         receipt_anchor,
                         receipt_coin,
+                        receipt_amount,
                         receipt_source,
                         receipt_target,
                         receipt_slot,
@@ -373,12 +404,14 @@ impl Database {
                             Some(txid),
                             Some(anchor),
                             Some(coin),
+                            Some(amount),
                             Some(source),
                             Some(target),
                             Some(slot),
                         ) => Some(ReceiptInfo {
                             anchor: encode_txid(&anchor),
                             coin,
+                            amount,
                             txid: encode_txid(&txid),
                             source,
                             target,
@@ -391,6 +424,7 @@ impl Database {
                         txid: encode_txid(&txid),
                         coin,
                         amount,
+                        fee,
                         recipient: encode_recipient(&recipient),
                         source,
                         target,
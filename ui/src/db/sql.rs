@@ -10,8 +10,8 @@ impl Database {
     pub fn insert_block(&self, block: &Block) -> Result<()> {
         let conn = self.conn.lock().unwrap();
 
-        // Serialize votes using postcard (since BTreeMap keys are byte arrays, not JSON-compatible)
-        let votes_bytes = postcard::to_allocvec(&block.header.votes)?;
+        // Serialize votes in the versioned envelope (since BTreeMap keys are byte arrays, not JSON-compatible)
+        let votes_bytes = zcore::codec::encode(&block.header.votes)?;
 
         // Compute block hash and tx count
         let hash = block.header.hash();
@@ -19,8 +19,8 @@ impl Database {
 
         // Insert block header
         conn.execute(
-            "INSERT OR REPLACE INTO blocks (slot, hash, parent, state, accumulator, extrinsic, votes, txns)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT OR REPLACE INTO blocks (slot, hash, parent, state, accumulator, extrinsic, votes, txns, version)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
                 block.header.slot,
                 &hash[..],
@@ -30,6 +30,7 @@ impl Database {
                 &block.header.extrinsic[..],
                 &votes_bytes[..],
                 txns,
+                block.header.version,
             ],
         )?;
 
@@ -42,15 +43,16 @@ impl Database {
                 let target_str = format!("{:?}", bridge.target);
 
                 conn.execute(
-                    "INSERT OR REPLACE INTO bridges 
-                     (txid, hash, coin, recipient, amount, source, target, block_slot, bundle_hash)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    "INSERT OR REPLACE INTO bridges
+                     (txid, hash, coin, recipient, amount, fee, source, target, slot, bundle_hash)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
                     params![
                         &bridge.txid[..],
                         &bridge_hash[..],
                         coin_str,
                         &bridge.recipient[..],
                         bridge.amount,
+                        bridge.fee,
                         source_str,
                         target_str,
                         block.header.slot,
@@ -67,13 +69,14 @@ impl Database {
             let target_str = format!("{:?}", receipt.target);
 
             conn.execute(
-                "INSERT OR REPLACE INTO receipts 
-                 (txid, anchor, coin, source, target, block_slot)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                "INSERT OR REPLACE INTO receipts
+                 (txid, anchor, coin, amount, source, target, slot)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
                 params![
                     &receipt.txid[..],
                     &receipt.anchor[..],
                     coin_str,
+                    receipt.amount,
                     source_str,
                     target_str,
                     block.header.slot,
@@ -92,11 +95,11 @@ impl Database {
                     if matches!(bridge.source, zcore::registry::Chain::Zcash)
                         && matches!(bridge.target, zcore::registry::Chain::Solana)
                     {
-                        zec_to_solana += bridge.amount;
+                        zec_to_solana += bridge.net();
                     } else if matches!(bridge.source, zcore::registry::Chain::Solana)
                         && matches!(bridge.target, zcore::registry::Chain::Zcash)
                     {
-                        zozec_to_zcash += bridge.amount;
+                        zozec_to_zcash += bridge.net();
                     }
                 }
             }
@@ -2,8 +2,7 @@
 
 use crate::db::Database;
 use anyhow::Result;
-use jsonrpsee::ws_client::WsClientBuilder;
-use rpc::ApiClient;
+use rpc::{Client, Encoding};
 
 /// Block subscriber that listens to new blocks from the RPC
 pub struct Subscriber {
@@ -22,12 +21,12 @@ impl Subscriber {
         tracing::info!("Connecting to Zosh RPC at {}", self.rpc_url);
 
         // Create WebSocket client
-        let client = WsClientBuilder::default().build(&self.rpc_url).await?;
+        let client = Client::connect(self.rpc_url.as_str()).await?;
         tracing::info!("Connected to Zosh RPC, subscribing to blocks...");
 
-        // Subscribe to blocks
-        let mut subscription = client.subscribe_block().await?;
-        while let Some(Ok(block)) = subscription.next().await {
+        // Subscribe to blocks, resubscribing on disconnection
+        let mut blocks = client.watch_blocks(Encoding::Base64);
+        while let Some(block) = blocks.recv().await {
             let block = block.into_block()?;
             tracing::info!("Received block at slot {}", block.header.slot);
 
@@ -8,6 +8,7 @@ use zcore::{ex::BridgeBundle, Block, Hash};
 /// UI representation of a block
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UIBlock {
+    pub version: u8,
     pub slot: u32,
     pub hash: String,
     pub parent: String,
@@ -22,6 +23,7 @@ impl UIBlock {
     pub fn from_block(block: &Block) -> Self {
         let hash = block.header.hash();
         Self {
+            version: block.header.version,
             slot: block.header.slot,
             hash: bs58::encode(hash).into_string(),
             parent: bs58::encode(block.header.parent).into_string(),
@@ -101,6 +103,7 @@ pub struct UIBridge {
     pub coin: String,
     pub recipient: String,
     pub amount: u64,
+    pub fee: u64,
     pub source: String,
     pub target: String,
     pub txid: String,
@@ -112,6 +115,7 @@ impl UIBridge {
             coin: format!("{:?}", bridge.coin),
             recipient: util::encode_recipient(&bridge.recipient),
             amount: bridge.amount,
+            fee: bridge.fee,
             source: format!("{:?}", bridge.source),
             target: format!("{:?}", bridge.target),
             txid: util::encode_txid(&bridge.txid),
@@ -124,6 +128,7 @@ impl UIBridge {
 pub struct UIReceipt {
     pub anchor: String,
     pub coin: String,
+    pub amount: u64,
     pub txid: String,
     pub source: String,
     pub target: String,
@@ -135,6 +140,7 @@ impl UIReceipt {
         Self {
             anchor: util::encode_txid(&receipt.anchor),
             coin: format!("{:?}", receipt.coin),
+            amount: receipt.amount,
             txid: util::encode_txid(&receipt.txid),
             source: format!("{:?}", receipt.source),
             target: format!("{:?}", receipt.target),
@@ -166,6 +172,7 @@ pub struct UITxn {
     pub txid: String,
     pub coin: String,
     pub amount: u64,
+    pub fee: u64,
     pub recipient: String,
     pub source: String,
     pub target: String,
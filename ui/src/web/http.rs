@@ -164,6 +164,7 @@ pub async fn get_txns(
             txid: tx.txid,
             coin: tx.coin,
             amount: tx.amount,
+            fee: tx.fee,
             recipient: tx.recipient,
             source: tx.source,
             target: tx.target,
@@ -171,6 +172,7 @@ pub async fn get_txns(
             receipt: tx.receipt.map(|r| UIReceipt {
                 anchor: r.anchor,
                 coin: r.coin,
+                amount: r.amount,
                 txid: r.txid,
                 source: r.source,
                 target: r.target,
@@ -4,7 +4,7 @@
 //! - HTTP: Returns immediately (data or 404)
 //! - WebSocket: Waits for data if not found, streams updates for stats
 //!
-//! Server runs on single address (e.g., http://localhost:1439 / ws://localhost:1439)
+//! Server runs on single address (e.g., http://localhost:1888 / ws://localhost:1888)
 
 pub mod http;
 pub mod ws;
@@ -207,6 +207,7 @@ pub fn build_tx_response(tx: BridgeTransactionResult) -> UITxn {
         txid: tx.txid,
         coin: tx.coin,
         amount: tx.amount,
+        fee: tx.fee,
         recipient: tx.recipient,
         source: tx.source,
         target: tx.target,
@@ -214,6 +215,7 @@ pub fn build_tx_response(tx: BridgeTransactionResult) -> UITxn {
         receipt: tx.receipt.map(|r| UIReceipt {
             anchor: r.anchor,
             coin: r.coin,
+            amount: r.amount,
             txid: r.txid,
             source: r.source,
             target: r.target,
@@ -1,9 +1,15 @@
 //! The block structure of zorch
 
-use crate::Extrinsic;
+use crate::{codec, legacy::LegacyBlock, Extrinsic};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+/// The current version of the header
+///
+/// Version 2 binds the transaction count into the extrinsic root.
+pub const HEADER_VERSION: u8 = 2;
+
 /// The block structure of zorch
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Block {
@@ -14,9 +20,26 @@ pub struct Block {
     pub extrinsic: Extrinsic,
 }
 
+impl Block {
+    /// Encode the block in the canonical versioned format
+    ///
+    /// `MAGIC || ENCODING_VERSION || postcard(block)`
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        codec::encode(self)
+    }
+
+    /// Decode the block, upgrading the older versions
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        codec::decode::<Self, LegacyBlock>(data)
+    }
+}
+
 /// The header structure of zorch
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Header {
+    /// The version of the header
+    pub version: u8,
+
     /// The height of the block
     pub slot: u32,
 
@@ -38,8 +61,14 @@ pub struct Header {
 
 impl Header {
     /// Compute the hash of the header
+    ///
+    /// The legacy headers (version 0) are hashed without the version.
     pub fn hash(&self) -> [u8; 32] {
-        let mut data = self.slot.to_le_bytes().to_vec();
+        let mut data = Vec::new();
+        if self.version > 0 {
+            data.push(self.version);
+        }
+        data.extend_from_slice(&self.slot.to_le_bytes());
         data.extend_from_slice(&self.parent);
         data.extend_from_slice(&self.state);
         data.extend_from_slice(&self.accumulator);
@@ -65,3 +94,44 @@ pub struct Head {
     /// The parent block hash
     pub hash: [u8; 32],
 }
+
+#[test]
+fn test_header_hash_vectors() {
+    let mut header = Header {
+        version: 0,
+        slot: 1,
+        parent: [1; 32],
+        state: [2; 32],
+        accumulator: [3; 32],
+        extrinsic: [4; 32],
+        votes: Default::default(),
+    };
+    assert_eq!(
+        hex::encode(header.hash()),
+        "162670bb67ab7cc77538677bfd58285d2efbbfa5ba69517aa30c443eaf8abecc"
+    );
+
+    header.version = 1;
+    assert_eq!(
+        hex::encode(header.hash()),
+        "c472e1b8ccc62dba1227a4058f53bc8fc9abaf74e8958b5959158d143860de9e"
+    );
+}
+
+#[test]
+fn test_block_decode_legacy() -> Result<()> {
+    let mut legacy = vec![1];
+    for byte in [1, 2, 3, 4] {
+        legacy.extend_from_slice(&[byte; 32]);
+    }
+    legacy.extend_from_slice(&[0, 0, 0]);
+
+    let block = Block::decode(&legacy)?;
+    assert_eq!(block.header.version, 0);
+    assert_eq!(block.header.slot, 1);
+    assert_eq!(block.header.extrinsic, [4; 32]);
+
+    let upgraded = Block::decode(&block.encode()?)?;
+    assert_eq!(upgraded.header.hash(), block.header.hash());
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! The versioned encoding of the stored and transmitted data
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The current version of the encoding
+pub const ENCODING_VERSION: u8 = 1;
+
+/// The magic prefix of the versioned encoding
+///
+/// Legacy data is raw postcard starting with a varint or an enum index, a
+/// varint can not continue on all of its first four bytes and end with a
+/// byte larger than `0x0f` within a u32, the longer ones would be lengths
+/// beyond any real data, so the prefix never collides with them.
+pub const MAGIC: [u8; 5] = [0xff, 0xff, 0xff, 0xff, b'Z'];
+
+/// Encode the value in the versioned envelope
+///
+/// `MAGIC || ENCODING_VERSION || postcard(value)`
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    let mut data = MAGIC.to_vec();
+    data.push(ENCODING_VERSION);
+    data.extend(postcard::to_allocvec(value)?);
+    Ok(data)
+}
+
+/// Decode the value in the versioned envelope
+///
+/// The data without the magic prefix is decoded as the legacy type `L`
+/// and upgraded.
+pub fn decode<T, L>(data: &[u8]) -> Result<T>
+where
+    T: DeserializeOwned,
+    L: DeserializeOwned + Into<T>,
+{
+    let Some(versioned) = data.strip_prefix(&MAGIC) else {
+        let legacy: L = postcard::from_bytes(data)?;
+        return Ok(legacy.into());
+    };
+
+    match versioned.split_first() {
+        Some((&ENCODING_VERSION, body)) => Ok(postcard::from_bytes(body)?),
+        Some((version, _)) => anyhow::bail!("Unsupported encoding version {version}"),
+        None => anyhow::bail!("Missing encoding version"),
+    }
+}
+
+#[test]
+fn test_codec_envelope() -> Result<()> {
+    type Votes = std::collections::BTreeMap<[u8; 32], Vec<u8>>;
+
+    let mut votes = Votes::new();
+    votes.insert([1; 32], vec![2; 64]);
+
+    let data = encode(&votes)?;
+    assert_eq!(data[..6], [0xff, 0xff, 0xff, 0xff, b'Z', ENCODING_VERSION]);
+    assert_eq!(decode::<Votes, Votes>(&data)?, votes);
+
+    // the raw postcard is decoded as the legacy type
+    let legacy = postcard::to_allocvec(&votes)?;
+    assert_eq!(decode::<Votes, Votes>(&legacy)?, votes);
+
+    let mut future = data.clone();
+    future[5] = ENCODING_VERSION + 1;
+    assert!(decode::<Votes, Votes>(&future).is_err());
+    Ok(())
+}
@@ -7,6 +7,9 @@ use crate::{
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+/// The length of a zcash nullifier
+pub const NULLIFIER_LENGTH: usize = 32;
+
 /// The signed bridge transactions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BridgeBundle {
@@ -16,7 +19,11 @@ pub struct BridgeBundle {
     /// The bridge transactions
     pub bridge: Vec<Bridge>,
 
-    /// The data we need for reconstructing the outer transaction
+    /// The data of the outer transaction
+    ///
+    /// Zcash bundles carry the nullifiers of all their orchard actions,
+    /// including the dummy spends padding the actions, the bundles of
+    /// other chains carry no data.
     pub data: Vec<u8>,
 
     /// The signatures for the upcoming outer transactions
@@ -29,6 +36,27 @@ impl BridgeBundle {
         let data = postcard::to_allocvec(&self)?;
         Ok(crypto::blake3(&data))
     }
+
+    /// Get the nullifiers of the notes spent by the bundle
+    ///
+    /// Zcash bundles carry the nullifiers of their orchard actions in
+    /// `data`, the bundles of other chains spend no notes. The random
+    /// nullifiers of the dummy spends never collide with the real ones.
+    pub fn nullifiers(&self) -> Vec<&[u8]> {
+        match self.target {
+            Chain::Zcash => self.data.chunks(NULLIFIER_LENGTH).collect(),
+            Chain::Solana => Vec::new(),
+        }
+    }
+
+    /// Check if the bundles spend the same zcash notes
+    pub fn conflicts(&self, other: &BridgeBundle) -> bool {
+        let nullifiers = self.nullifiers();
+        other
+            .nullifiers()
+            .iter()
+            .any(|nullifier| nullifiers.contains(nullifier))
+    }
 }
 
 impl BridgeBundle {
@@ -52,9 +80,12 @@ pub struct Bridge {
     /// The recipient address
     pub recipient: Vec<u8>,
 
-    /// The amount of the transaction
+    /// The gross amount of the transaction
     pub amount: u64,
 
+    /// The bridge fee charged from the gross amount
+    pub fee: u64,
+
     /// The source of the transaction
     pub source: Chain,
 
@@ -71,6 +102,11 @@ impl Bridge {
         let data = postcard::to_allocvec(&self)?;
         Ok(crypto::blake3(&data))
     }
+
+    /// The net amount delivered to the recipient
+    pub fn net(&self) -> u64 {
+        self.amount.saturating_sub(self.fee)
+    }
 }
 
 /// The confirmation of the bridge transaction
@@ -82,6 +118,9 @@ pub struct Receipt {
     /// The coin of the transaction
     pub coin: Coin,
 
+    /// The net amount delivered to the recipient
+    pub amount: u64,
+
     /// The signature of the confirmation transaction
     pub txid: Vec<u8>,
 
@@ -91,3 +130,59 @@ pub struct Receipt {
     /// The target chain of the transaction
     pub target: Chain,
 }
+
+impl Receipt {
+    /// The key of the receipt in the transactions of the block
+    ///
+    /// The receipts of a multi-bridge bundle share the txid of their
+    /// transaction, so they are keyed by the txid followed by the anchor.
+    pub fn key(&self) -> Vec<u8> {
+        [&self.txid[..], &self.anchor[..]].concat()
+    }
+}
+
+#[test]
+fn test_bridge_hash_vectors() -> Result<()> {
+    let bridge = Bridge {
+        coin: Coin::Zec,
+        recipient: vec![5; 32],
+        amount: 100_000_000,
+        fee: 100_000,
+        source: Chain::Zcash,
+        target: Chain::Solana,
+        txid: vec![6; 32],
+    };
+    assert_eq!(
+        hex::encode(bridge.hash()?),
+        "90e5bd6ad2f25268e004d7a056f6036397a4f9e506e47da30080b4244b86a1a3"
+    );
+
+    let bundle = BridgeBundle {
+        target: Chain::Solana,
+        bridge: vec![bridge],
+        data: vec![7; 32],
+        signatures: vec![vec![8; 64]],
+    };
+    assert_eq!(
+        hex::encode(bundle.hash()?),
+        "735e2231f98aaf9350eaa6130f827f58b2e74518fbf134427eb3d6925627081f"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_bundle_conflicts() {
+    let mut bundle = BridgeBundle::new(Chain::Zcash);
+    bundle.data = [[1; 32], [2; 32]].concat();
+
+    let mut other = BridgeBundle::new(Chain::Zcash);
+    other.data = [[3; 32], [2; 32]].concat();
+    assert!(bundle.conflicts(&other));
+
+    other.data = [3; 32].to_vec();
+    assert!(!bundle.conflicts(&other));
+
+    let mut solana = BridgeBundle::new(Chain::Solana);
+    solana.data = [1; 32].to_vec();
+    assert!(!bundle.conflicts(&solana));
+}
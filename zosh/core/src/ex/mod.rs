@@ -27,7 +27,19 @@ impl Extrinsic {
             + self.receipts.len()
     }
 
+    /// Get the bridge requests of the extrinsic
+    pub fn bridges(&self) -> impl Iterator<Item = &Bridge> {
+        self.bridge.values().flat_map(|bundle| bundle.bridge.iter())
+    }
+
+    /// Get the merkle root of the signatures bound with their count
+    pub fn root(&self) -> Hash {
+        crypto::merkle::commit(self.txs())
+    }
+
     /// Get the signatures of the extrinsic
+    ///
+    /// The receipts are keyed by [`Receipt::key`].
     pub fn txs(&self) -> Vec<Vec<u8>> {
         let mut signatures = Vec::new();
         for bundle in self.bridge.values() {
@@ -37,7 +49,7 @@ impl Extrinsic {
         }
 
         for receipt in &self.receipts {
-            signatures.push(receipt.txid.clone());
+            signatures.push(receipt.key());
         }
 
         signatures.sort();
@@ -0,0 +1,149 @@
+//! The legacy (version 0) encoding of the blocks
+//!
+//! Blocks were encoded as raw postcard without version before the
+//! header version was introduced, the bridges had no fee and the
+//! receipts had no amount at that time.
+//!
+//! The upgraded bundles stay keyed by their legacy hashes, which no
+//! longer equal [`BridgeBundle::hash`] of the upgraded bundles.
+
+use crate::{
+    ex::{Bridge, BridgeBundle, Receipt},
+    registry::{Chain, Coin},
+    Block, Extrinsic, Hash, Header,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// The legacy block
+#[derive(Deserialize)]
+pub struct LegacyBlock {
+    header: LegacyHeader,
+    extrinsic: LegacyExtrinsic,
+}
+
+/// The legacy header without version
+#[derive(Deserialize)]
+struct LegacyHeader {
+    slot: u32,
+    parent: [u8; 32],
+    state: [u8; 32],
+    accumulator: [u8; 32],
+    extrinsic: [u8; 32],
+    votes: BTreeMap<[u8; 32], Vec<u8>>,
+}
+
+/// The legacy extrinsic
+#[derive(Deserialize)]
+struct LegacyExtrinsic {
+    bridge: BTreeMap<Hash, LegacyBundle>,
+    receipts: Vec<LegacyReceipt>,
+}
+
+/// The legacy bridge bundle
+#[derive(Deserialize)]
+struct LegacyBundle {
+    target: Chain,
+    bridge: Vec<LegacyBridge>,
+    data: Vec<u8>,
+    signatures: Vec<Vec<u8>>,
+}
+
+/// The legacy bridge without fee
+#[derive(Deserialize)]
+struct LegacyBridge {
+    coin: Coin,
+    recipient: Vec<u8>,
+    amount: u64,
+    source: Chain,
+    target: Chain,
+    txid: Vec<u8>,
+}
+
+/// The legacy receipt without amount
+#[derive(Deserialize)]
+struct LegacyReceipt {
+    anchor: Vec<u8>,
+    coin: Coin,
+    txid: Vec<u8>,
+    source: Chain,
+    target: Chain,
+}
+
+impl From<LegacyBlock> for Block {
+    fn from(legacy: LegacyBlock) -> Self {
+        let LegacyHeader {
+            slot,
+            parent,
+            state,
+            accumulator,
+            extrinsic,
+            votes,
+        } = legacy.header;
+        let header = Header {
+            version: 0,
+            slot,
+            parent,
+            state,
+            accumulator,
+            extrinsic,
+            votes,
+        };
+
+        let bridge = legacy
+            .extrinsic
+            .bridge
+            .into_iter()
+            .map(|(hash, bundle)| (hash, bundle.into()))
+            .collect();
+        let receipts = legacy
+            .extrinsic
+            .receipts
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        Block {
+            header,
+            extrinsic: Extrinsic { bridge, receipts },
+        }
+    }
+}
+
+impl From<LegacyBundle> for BridgeBundle {
+    fn from(legacy: LegacyBundle) -> Self {
+        Self {
+            target: legacy.target,
+            bridge: legacy.bridge.into_iter().map(Into::into).collect(),
+            data: legacy.data,
+            signatures: legacy.signatures,
+        }
+    }
+}
+
+impl From<LegacyBridge> for Bridge {
+    fn from(legacy: LegacyBridge) -> Self {
+        Self {
+            coin: legacy.coin,
+            recipient: legacy.recipient,
+            amount: legacy.amount,
+            fee: 0,
+            source: legacy.source,
+            target: legacy.target,
+            txid: legacy.txid,
+        }
+    }
+}
+
+impl From<LegacyReceipt> for Receipt {
+    /// NOTE: the delivered amount was not recorded in the legacy receipts
+    fn from(legacy: LegacyReceipt) -> Self {
+        Self {
+            anchor: legacy.anchor,
+            coin: legacy.coin,
+            amount: 0,
+            txid: legacy.txid,
+            source: legacy.source,
+            target: legacy.target,
+        }
+    }
+}
@@ -1,15 +1,21 @@
 //! Core types for the zorch network
 
 pub use {
-    block::{Block, Head, Header},
+    block::{Block, Head, Header, HEADER_VERSION},
+    codec::ENCODING_VERSION,
     ex::Extrinsic,
+    proof::InclusionProof,
     state::State,
     util::{FixedBytes, Message},
 };
 
 pub mod bft;
 mod block;
+pub mod codec;
 pub mod ex;
+mod legacy;
+pub mod metrics;
+mod proof;
 pub mod registry;
 pub mod state;
 pub mod util;
@@ -0,0 +1,58 @@
+//! The metrics shared by the node services
+
+use std::time::Duration;
+
+/// The latency histogram
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    /// The upper bounds of the buckets in seconds
+    pub bounds: &'static [f64],
+
+    /// The counts of the buckets, not cumulative
+    ///
+    /// The last bucket counts the observations slower than all bounds.
+    pub buckets: Vec<u64>,
+
+    /// The total latency in seconds
+    pub sum: f64,
+
+    /// The number of observations
+    pub count: u64,
+}
+
+impl Histogram {
+    /// Create a new histogram with the bucket bounds
+    pub fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: vec![0; bounds.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    /// Observe a latency
+    pub fn observe(&mut self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.buckets[bucket] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[test]
+fn test_histogram_observe() {
+    let mut histogram = Histogram::new(&[0.5, 1.0]);
+    histogram.observe(Duration::from_millis(500));
+    histogram.observe(Duration::from_millis(750));
+    histogram.observe(Duration::from_secs(2));
+
+    assert_eq!(histogram.buckets, [1, 1, 1]);
+    assert_eq!(histogram.count, 3);
+    assert_eq!(histogram.sum, 3.25);
+}
@@ -0,0 +1,137 @@
+//! The inclusion proof of the transactions
+
+use crate::{bft::Bft, Block, Header};
+use anyhow::Result;
+use crypto::merkle;
+use serde::{Deserialize, Serialize};
+
+/// The first header version binding the transaction count into the
+/// extrinsic root
+const BOUND_VERSION: u8 = 2;
+
+/// The proof of a transaction included in a finalized block
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InclusionProof {
+    /// The finalized header with its votes
+    pub header: Header,
+
+    /// The txid of the transaction
+    pub txid: Vec<u8>,
+
+    /// The index of the txid in the sorted transactions of the block
+    pub index: u32,
+
+    /// The number of the transactions in the block
+    pub count: u32,
+
+    /// The merkle path from the txid to the extrinsic root
+    pub path: Vec<Vec<u8>>,
+}
+
+impl InclusionProof {
+    /// Create the inclusion proof of the txid in the block
+    pub fn new(block: &Block, txid: &[u8]) -> Result<Self> {
+        let txs = block.extrinsic.txs();
+        let Ok(index) = txs.binary_search_by(|tx| tx.as_slice().cmp(txid)) else {
+            anyhow::bail!("Transaction is not included in block {}", block.header.slot);
+        };
+
+        let count = txs.len();
+        let path = merkle::proof(txs, index, crypto::blake3)
+            .ok_or_else(|| anyhow::anyhow!("Invalid transaction index {index}"))?;
+        Ok(Self {
+            header: block.header.clone(),
+            txid: txid.to_vec(),
+            index: index as u32,
+            count: count as u32,
+            path,
+        })
+    }
+
+    /// Verify the merkle path and the votes of the header
+    ///
+    /// The count of the older headers is not bound into their extrinsic
+    /// roots, an inner node would verify as a txid, so they are rejected.
+    pub fn verify(&self, bft: &Bft) -> Result<()> {
+        if self.header.version < BOUND_VERSION {
+            anyhow::bail!(
+                "Unbound extrinsic root of header version {} in block {}",
+                self.header.version,
+                self.header.slot
+            );
+        }
+
+        if !merkle::verify_bound(
+            &self.header.extrinsic,
+            &self.txid,
+            self.index as usize,
+            self.count as usize,
+            &self.path,
+            crypto::blake3,
+        ) {
+            anyhow::bail!("Invalid merkle path in block {}", self.header.slot);
+        }
+
+        bft.validate_votes(&self.header)
+    }
+}
+
+#[test]
+fn test_inclusion_proof() -> Result<()> {
+    use crate::{
+        ex::{Bridge, BridgeBundle, Receipt},
+        registry::{Chain, Coin},
+    };
+
+    let mut block = Block::default();
+    let mut bundle = BridgeBundle::new(Chain::Solana);
+    for byte in 1..4 {
+        bundle.bridge.push(Bridge {
+            coin: Coin::Zec,
+            recipient: vec![byte; 32],
+            amount: 100,
+            fee: 1,
+            source: Chain::Zcash,
+            target: Chain::Solana,
+            txid: vec![byte; 32],
+        });
+    }
+    block.extrinsic.bridge.insert([0; 32], bundle);
+    block.extrinsic.receipts.push(Receipt {
+        anchor: vec![9; 32],
+        coin: Coin::Zec,
+        amount: 99,
+        txid: vec![8; 64],
+        source: Chain::Zcash,
+        target: Chain::Solana,
+    });
+    block.header.version = crate::HEADER_VERSION;
+    block.header.extrinsic = block.extrinsic.root();
+
+    let bft = Bft::default();
+    for txid in block.extrinsic.txs() {
+        InclusionProof::new(&block, &txid)?.verify(&bft)?;
+    }
+    assert!(InclusionProof::new(&block, &[7; 32]).is_err());
+
+    let mut proof = InclusionProof::new(&block, &[2; 32])?;
+    proof.txid = vec![7; 32];
+    assert!(proof.verify(&bft).is_err());
+
+    // the count is bound into the extrinsic root
+    let mut proof = InclusionProof::new(&block, &[2; 32])?;
+    proof.count -= 1;
+    assert!(proof.verify(&bft).is_err());
+
+    let mut proof = InclusionProof::new(&block, &[2; 32])?;
+    proof.header.version = 1;
+    assert!(proof.verify(&bft).is_err());
+
+    let proof = InclusionProof::new(&block, &[2; 32])?;
+    let bft = Bft {
+        threshold: 1,
+        ..Default::default()
+    };
+    assert!(proof.verify(&bft).is_err());
+    Ok(())
+}
@@ -18,7 +18,7 @@ impl Chain {
     pub fn max_bundle_size(&self) -> usize {
         match self {
             Chain::Solana => 10,
-            Chain::Zcash => 1,
+            Chain::Zcash => 8,
         }
     }
 }
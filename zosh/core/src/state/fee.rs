@@ -0,0 +1,97 @@
+//! The bridge fee schedule
+
+use crate::registry::Chain;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The denominator of the basis points
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// The fee charged on a bridge direction
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Fee {
+    /// The flat fee charged on every bridge request
+    pub flat: u64,
+
+    /// The variable fee in basis points of the gross amount
+    pub bps: u16,
+
+    /// The minimum fee of a bridge request
+    pub min: u64,
+}
+
+impl Fee {
+    /// Compute the fee of the gross amount
+    pub fn charge(&self, amount: u64) -> Result<u64> {
+        let variable = (amount as u128 * self.bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        let fee = self.flat.saturating_add(variable).max(self.min);
+        if fee >= amount {
+            anyhow::bail!("Amount {amount} can not cover the bridge fee {fee}");
+        }
+
+        Ok(fee)
+    }
+}
+
+/// The fee schedule of the bridge
+///
+/// The direction is identified by the target chain since we only
+/// have two chains at the moment.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    /// The fee of the bridges targeting solana (zcash -> solana)
+    pub solana: Fee,
+
+    /// The fee of the bridges targeting zcash (solana -> zcash)
+    pub zcash: Fee,
+}
+
+impl FeeSchedule {
+    /// Get the fee of the target chain
+    pub fn get(&self, target: Chain) -> &Fee {
+        match target {
+            Chain::Solana => &self.solana,
+            Chain::Zcash => &self.zcash,
+        }
+    }
+}
+
+/// The collected fees indexed by the target chain
+pub type FeeTotals = BTreeMap<Chain, u64>;
+
+/// Add the fee of a bridge request to the collected fees
+pub fn collect(totals: &mut FeeTotals, target: Chain, fee: u64) -> Result<()> {
+    let total = totals.entry(target).or_default();
+    *total = total
+        .checked_add(fee)
+        .ok_or_else(|| anyhow::anyhow!("Collected fees of {target:?} overflow"))?;
+    Ok(())
+}
+
+#[test]
+fn test_fee_charge() -> Result<()> {
+    let fee = Fee {
+        flat: 1_000,
+        bps: 30,
+        min: 10_000,
+    };
+
+    assert_eq!(fee.charge(100_000)?, 10_000);
+    assert_eq!(fee.charge(100_000_000)?, 301_000);
+    assert!(fee.charge(10_000).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_fee_collect() -> Result<()> {
+    let mut totals = FeeTotals::default();
+    collect(&mut totals, Chain::Zcash, 10_000)?;
+    collect(&mut totals, Chain::Zcash, 20_000)?;
+    assert_eq!(totals[&Chain::Zcash], 30_000);
+
+    collect(&mut totals, Chain::Solana, u64::MAX)?;
+    assert!(collect(&mut totals, Chain::Solana, 1).is_err());
+    assert_eq!(totals[&Chain::Solana], u64::MAX);
+    Ok(())
+}
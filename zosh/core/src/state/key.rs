@@ -17,3 +17,12 @@ pub const PRESENT_KEY: [u8; 31] = to_key!(1);
 
 /// The key for the accumulator state
 pub const ACCUMULATOR_KEY: [u8; 31] = to_key!(2);
+
+/// The key for the fee schedule state
+pub const FEE_KEY: [u8; 31] = to_key!(3);
+
+/// The key for the collected fees state
+pub const COLLECTED_KEY: [u8; 31] = to_key!(4);
+
+/// The key for the block size limits state
+pub const LIMITS_KEY: [u8; 31] = to_key!(5);
@@ -0,0 +1,151 @@
+//! The block size limits
+
+use crate::{ex::BridgeBundle, registry::Chain, Extrinsic, Hash};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// The encoding overhead of the extrinsic, the lengths of the
+/// bundle map and the receipt list in varint.
+pub const EXTRINSIC_OVERHEAD: usize = 10;
+
+/// The size limits of a block
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of bridge bundles in a block
+    pub bundles: u32,
+
+    /// The maximum number of receipts in a block
+    pub receipts: u32,
+
+    /// The maximum encoded bytes of the extrinsic of a block
+    pub bytes: u32,
+
+    /// The maximum number of bridges targeting solana in a block
+    pub solana: u32,
+
+    /// The maximum number of bridges targeting zcash in a block
+    pub zcash: u32,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            bundles: 64,
+            receipts: 1024,
+            bytes: 1024 * 1024,
+            solana: 640,
+            zcash: 64,
+        }
+    }
+}
+
+impl Limits {
+    /// Get the maximum number of bridges of the target chain
+    pub fn bridges(&self, target: Chain) -> u32 {
+        match target {
+            Chain::Solana => self.solana,
+            Chain::Zcash => self.zcash,
+        }
+    }
+
+    /// Validate that the bundle fits in an empty block
+    ///
+    /// The oversize bundles can never be packed, they are rejected before
+    /// signing and pooling.
+    pub fn validate_bundle(&self, bundle: &BridgeBundle) -> Result<()> {
+        let bridges = bundle.bridge.len();
+        if bridges > self.bridges(bundle.target) as usize {
+            anyhow::bail!(
+                "Too many bridges in bundle targeting {:?}: max={}, got={bridges}",
+                bundle.target,
+                self.bridges(bundle.target)
+            );
+        }
+
+        // the bundles are keyed by their hashes in the extrinsic
+        let max_bytes = (self.bytes as usize).saturating_sub(EXTRINSIC_OVERHEAD);
+        let bytes = std::mem::size_of::<Hash>() + postcard::to_allocvec(bundle)?.len();
+        if bytes > max_bytes {
+            anyhow::bail!("Oversize bundle: max={max_bytes}, got={bytes}");
+        }
+
+        Ok(())
+    }
+
+    /// Validate the extrinsic against the limits
+    pub fn validate(&self, extrinsic: &Extrinsic) -> Result<()> {
+        if extrinsic.bridge.len() > self.bundles as usize {
+            anyhow::bail!(
+                "Too many bundles: max={}, got={}",
+                self.bundles,
+                extrinsic.bridge.len()
+            );
+        }
+
+        if extrinsic.receipts.len() > self.receipts as usize {
+            anyhow::bail!(
+                "Too many receipts: max={}, got={}",
+                self.receipts,
+                extrinsic.receipts.len()
+            );
+        }
+
+        for target in [Chain::Solana, Chain::Zcash] {
+            let bridges = extrinsic.bridges().filter(|b| b.target == target).count();
+            if bridges > self.bridges(target) as usize {
+                anyhow::bail!(
+                    "Too many bridges targeting {target:?}: max={}, got={bridges}",
+                    self.bridges(target)
+                );
+            }
+        }
+
+        let bytes = postcard::to_allocvec(extrinsic)?.len();
+        if bytes > self.bytes as usize {
+            anyhow::bail!("Oversize extrinsic: max={}, got={bytes}", self.bytes);
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_limits_validate() -> Result<()> {
+    use crate::ex::{BridgeBundle, Receipt};
+    use crate::registry::Coin;
+
+    let receipt = Receipt {
+        anchor: vec![0; 32],
+        coin: Coin::Zec,
+        amount: 1,
+        txid: vec![0; 64],
+        source: Chain::Zcash,
+        target: Chain::Solana,
+    };
+
+    let mut extrinsic = Extrinsic::default();
+    extrinsic
+        .bridge
+        .insert([0; 32], BridgeBundle::new(Chain::Solana));
+    extrinsic.receipts = vec![receipt; 2];
+
+    let limits = Limits {
+        bundles: 1,
+        receipts: 2,
+        ..Default::default()
+    };
+    limits.validate(&extrinsic)?;
+
+    let limits = Limits {
+        receipts: 1,
+        ..Default::default()
+    };
+    assert!(limits.validate(&extrinsic).is_err());
+
+    let limits = Limits {
+        bytes: 64,
+        ..Default::default()
+    };
+    assert!(limits.validate(&extrinsic).is_err());
+    Ok(())
+}
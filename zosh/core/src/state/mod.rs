@@ -2,8 +2,14 @@
 
 use crate::{bft, Hash, Head};
 use serde::{Deserialize, Serialize};
+pub use {
+    fee::{collect, Fee, FeeSchedule, FeeTotals},
+    limits::{Limits, EXTRINSIC_OVERHEAD},
+};
 
+mod fee;
 pub mod key;
+mod limits;
 
 /// The state of the zosh network
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -16,4 +22,13 @@ pub struct State {
 
     /// The accumulator of all processed transactions
     pub accumulator: Hash,
+
+    /// The fee schedule of the bridge
+    pub fee: FeeSchedule,
+
+    /// The total fees collected by the bridge
+    pub collected: FeeTotals,
+
+    /// The size limits of the blocks
+    pub limits: Limits,
 }
@@ -17,6 +17,21 @@ pub fn hroot(hashes: Vec<Vec<u8>>, hash: fn(&[u8]) -> [u8; 32]) -> [u8; 32] {
     root
 }
 
+/// Bind the leaf count into the root.
+///
+/// The leaves are not hashed with a prefix distinct from the inner nodes,
+/// so an inner node verifies as a leaf of a smaller tree, binding the count
+/// rules out the smaller trees.
+pub fn bind(root: &[u8; 32], count: usize, hash: fn(&[u8]) -> [u8; 32]) -> [u8; 32] {
+    hash(&[b"count".as_slice(), &(count as u64).to_le_bytes(), root].concat())
+}
+
+/// Compute the root of a Merkle tree bound with the leaf count.
+pub fn commit(leaves: Vec<Vec<u8>>) -> [u8; 32] {
+    let count = leaves.len();
+    bind(&root(leaves), count, crate::blake3)
+}
+
 /// Compute the Merkle tree.
 pub fn tree(leaves: Vec<Vec<u8>>, hash: fn(&[u8]) -> [u8; 32]) -> Vec<Vec<Vec<u8>>> {
     if leaves.is_empty() {
@@ -53,3 +68,165 @@ pub fn tree(leaves: Vec<Vec<u8>>, hash: fn(&[u8]) -> [u8; 32]) -> Vec<Vec<Vec<u8
 
     tree
 }
+
+/// Compute the Merkle path of the leaf at the index.
+///
+/// The path is the list of siblings from the leaf to the root, the
+/// promoted odd nodes have no sibling and are skipped.
+pub fn proof(
+    leaves: Vec<Vec<u8>>,
+    index: usize,
+    hash: fn(&[u8]) -> [u8; 32],
+) -> Option<Vec<Vec<u8>>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut path = Vec::new();
+    if leaves.len() == 1 {
+        return Some(path);
+    }
+
+    let mut index = index;
+    let mut layers = vec![leaves.clone()];
+    layers.extend(tree(leaves, hash));
+    for layer in &layers[..layers.len() - 1] {
+        if let Some(sibling) = layer.get(index ^ 1) {
+            path.push(sibling.clone());
+        }
+        index /= 2;
+    }
+
+    Some(path)
+}
+
+/// Verify the Merkle path of the leaf against the root.
+///
+/// NOTE: the count comes from the prover, use [`verify_bound`] against the
+/// roots bound with the count.
+pub fn verify(
+    root: &[u8; 32],
+    leaf: &[u8],
+    index: usize,
+    count: usize,
+    path: &[Vec<u8>],
+    hash: fn(&[u8]) -> [u8; 32],
+) -> bool {
+    walk(leaf, index, count, path, hash).is_some_and(|node| node == *root)
+}
+
+/// Verify the Merkle path of the leaf against the root bound with the
+/// count, see [`bind`].
+pub fn verify_bound(
+    commitment: &[u8; 32],
+    leaf: &[u8],
+    index: usize,
+    count: usize,
+    path: &[Vec<u8>],
+    hash: fn(&[u8]) -> [u8; 32],
+) -> bool {
+    walk(leaf, index, count, path, hash).is_some_and(|node| bind(&node, count, hash) == *commitment)
+}
+
+/// Compute the root from the Merkle path of the leaf.
+///
+/// The path must have exactly one sibling per layer with a sibling.
+fn walk(
+    leaf: &[u8],
+    index: usize,
+    count: usize,
+    path: &[Vec<u8>],
+    hash: fn(&[u8]) -> [u8; 32],
+) -> Option<[u8; 32]> {
+    if index >= count {
+        return None;
+    }
+
+    if count == 1 {
+        return path.is_empty().then(|| hash(leaf));
+    }
+
+    let (mut index, mut count) = (index, count);
+    let mut node = leaf.to_vec();
+    let mut siblings = path.iter();
+    while count > 1 {
+        if index ^ 1 < count {
+            let sibling = siblings.next()?;
+            node = if index % 2 == 0 {
+                hash(&[b"node", node.as_slice(), sibling.as_slice()].concat()).to_vec()
+            } else {
+                hash(&[b"node", sibling.as_slice(), node.as_slice()].concat()).to_vec()
+            };
+        }
+
+        index /= 2;
+        count = count.div_ceil(2);
+    }
+
+    if siblings.next().is_some() {
+        return None;
+    }
+
+    node.try_into().ok()
+}
+
+#[test]
+fn test_merkle_proof() {
+    for count in 1..10 {
+        let leaves: Vec<Vec<u8>> = (0..count).map(|i| vec![i as u8; 64]).collect();
+        let root = root(leaves.clone());
+        for (index, leaf) in leaves.iter().enumerate() {
+            let path = proof(leaves.clone(), index, crate::blake3).expect("leaf exists");
+            assert!(verify(&root, leaf, index, count, &path, crate::blake3));
+            assert!(!verify(
+                &root,
+                &[0xff; 64],
+                index,
+                count,
+                &path,
+                crate::blake3
+            ));
+        }
+        assert!(proof(leaves, count, crate::blake3).is_none());
+    }
+}
+
+#[test]
+fn test_merkle_bound_count() {
+    let leaves: Vec<Vec<u8>> = (0..4).map(|i| vec![i as u8; 32]).collect();
+    let (root, commitment) = (root(leaves.clone()), commit(leaves.clone()));
+
+    // an inner node presented as a leaf of a smaller tree
+    let inner = tree(leaves.clone(), crate::blake3);
+    let (node, path) = (&inner[0][0], vec![inner[0][1].clone()]);
+    assert!(verify(&root, node, 0, 2, &path, crate::blake3));
+    assert!(!verify_bound(&commitment, node, 0, 2, &path, crate::blake3));
+
+    for (index, leaf) in leaves.iter().enumerate() {
+        let path = proof(leaves.clone(), index, crate::blake3).expect("leaf exists");
+        assert!(verify_bound(
+            &commitment,
+            leaf,
+            index,
+            4,
+            &path,
+            crate::blake3
+        ));
+        assert!(!verify_bound(
+            &commitment,
+            leaf,
+            index,
+            3,
+            &path,
+            crate::blake3
+        ));
+        assert!(!verify_bound(
+            &commitment,
+            leaf,
+            index,
+            4,
+            &path[1..],
+            crate::blake3
+        ));
+    }
+}
@@ -1,5 +1,5 @@
 //! Merkle tree related primitives
 
-pub use binary::root;
+pub use binary::{bind, commit, proof, root, verify, verify_bound};
 
 mod binary;
@@ -0,0 +1,117 @@
+//! RPC command line interface
+
+#![cfg(feature = "rpc")]
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use rpc::{BlockId, Client, Encoding, RpcInclusionProof};
+use serde::Serialize;
+use serde_json::json;
+
+/// RPC command line interface
+#[derive(Parser)]
+pub struct Rpc {
+    /// The websocket URL of the node
+    #[clap(short, long, default_value = "ws://127.0.0.1:1439")]
+    pub url: String,
+
+    /// The bearer token of the protected methods
+    #[clap(long)]
+    pub token: Option<String>,
+
+    #[clap(subcommand)]
+    pub command: RpcCommand,
+}
+
+/// The RPC commands
+#[derive(Subcommand)]
+pub enum RpcCommand {
+    /// Get the chain state
+    Chain,
+
+    /// Get a block by its slot or base58 hash
+    Block {
+        /// The slot or the base58 hash of the block
+        id: BlockId,
+    },
+
+    /// Get a bridge request and its receipt by the source txid
+    Tx {
+        /// The source txid, hex for zcash and base58 for solana
+        txid: String,
+    },
+
+    /// Get and verify the inclusion proof of a txid
+    Proof {
+        /// The txid, hex for zcash and base58 for solana
+        txid: String,
+
+        /// The source txid of the bridge request, for proving its receipt
+        #[clap(long)]
+        anchor: Option<String>,
+    },
+
+    /// Watch the new blocks
+    WatchBlocks,
+
+    /// Get the status of the mempool
+    Pool,
+}
+
+impl Rpc {
+    /// Run the RPC command
+    pub async fn run(&self) -> Result<()> {
+        let client = Client::connect_with_token(self.url.as_str(), self.token.as_deref()).await?;
+        match &self.command {
+            RpcCommand::Chain => print(&client.chain().await?),
+            RpcCommand::Block { id } => print(&client.block(id.clone(), Encoding::Json).await?),
+            RpcCommand::Tx { txid } => print(&json!({
+                "bridge": client.bridge(txid).await?,
+                "receipt": client.receipt(txid).await?,
+            })),
+            RpcCommand::Proof { txid, anchor } => {
+                self.proof(&client, txid, anchor.as_deref()).await
+            }
+            RpcCommand::WatchBlocks => self.watch_blocks(&client).await,
+            RpcCommand::Pool => print(&client.pool_status().await?),
+        }
+    }
+
+    /// Print the inclusion proof verified against the current validators
+    async fn proof(&self, client: &Client, txid: &str, anchor: Option<&str>) -> Result<()> {
+        let Some(proof) = client.inclusion_proof(txid, anchor).await? else {
+            anyhow::bail!("Transaction {txid} is not finalized");
+        };
+
+        let bft = client.chain().await?.bft;
+        print(&json!({
+            "proof": RpcInclusionProof::from(&proof),
+            "finalized": proof.verify(&bft).is_ok(),
+        }))
+    }
+
+    /// Print the headers of the new blocks
+    async fn watch_blocks(&self, client: &Client) -> Result<()> {
+        let mut blocks = client.watch_blocks(Encoding::Json);
+        while let Some(block) = blocks.recv().await {
+            let rpc::BlockInterface::Json(block) = block else {
+                continue;
+            };
+
+            println!(
+                "slot={} hash={} bundles={} receipts={}",
+                block.header.slot,
+                block.header.hash,
+                block.bundles.len(),
+                block.receipts.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Pretty print the value in JSON
+fn print(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
@@ -2,7 +2,7 @@
 
 use crate::dev::Dev;
 use anyhow::Result;
-use clap::Parser;
+use clap::{Args, Parser};
 use shadow_rs::{concatcp, shadow};
 use std::net::SocketAddr;
 use sync::{
@@ -11,6 +11,8 @@ use sync::{
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
+mod client;
+
 shadow!(build);
 
 const VERSION: &str = concatcp!(
@@ -43,7 +45,7 @@ impl App {
         self.init_tracing()?;
         self.create_dirs()?;
         match &self.command {
-            Command::Dev { address } => Dev::new().await?.start(*address).await,
+            Command::Dev { address, rpc } => Dev::new().await?.start(*address, rpc.clone()).await,
             Command::Solana(solana) => {
                 let config = Config::load()?;
                 solana.run(&config).await
@@ -52,6 +54,8 @@ impl App {
                 let config = Config::load()?;
                 zcash.run(&config).await
             }
+            #[cfg(feature = "rpc")]
+            Command::Rpc(rpc) => rpc.run().await,
         }?;
 
         Ok(())
@@ -94,9 +98,13 @@ impl App {
 pub enum Command {
     /// Development commanm
     Dev {
-        /// The address to bind the development node to
-        #[clap(short, long, default_value = "0.0.0.0:1439")]
+        /// The address to bind the UI service to
+        #[clap(short, long, default_value = "0.0.0.0:1888")]
         address: SocketAddr,
+
+        /// The JSON-RPC service options
+        #[clap(flatten)]
+        rpc: RpcArgs,
     },
 
     /// Solana command
@@ -106,4 +114,32 @@ pub enum Command {
     /// Zcash command
     #[clap(subcommand)]
     Zcash(zcash::Zcash),
+
+    /// Query a running node over RPC
+    #[cfg(feature = "rpc")]
+    Rpc(client::Rpc),
+}
+
+/// The JSON-RPC service options
+#[derive(Args, Clone)]
+pub struct RpcArgs {
+    /// The address to bind the JSON-RPC service to
+    #[clap(short, long, default_value = "0.0.0.0:1439")]
+    pub rpc: SocketAddr,
+
+    /// The bearer token required by the submission methods
+    #[clap(long)]
+    pub rpc_token: Option<String>,
+
+    /// The requests per second of an IP, 0 for unlimited
+    #[clap(long, default_value = "100")]
+    pub rpc_ip_rate: u32,
+
+    /// The bridge submissions per second of an IP, 0 for unlimited
+    #[clap(long, default_value = "5")]
+    pub rpc_submit_rate: u32,
+
+    /// The maximum active subscriptions of a connection
+    #[clap(long, default_value = "64")]
+    pub rpc_max_subscriptions: usize,
 }
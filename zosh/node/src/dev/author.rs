@@ -2,10 +2,16 @@
 
 use crate::dev::Development;
 use anyhow::Result;
-use runtime::{Hook, Runtime};
+use runtime::{Hook, Runtime, Storage};
 use solana_signer::Signer;
-use std::time::{Duration, Instant};
-use sync::solana::dev;
+use std::{
+    mem,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use sync::{solana::dev, InvalidReceipt, Sync};
+use tokio::sync::Mutex;
+use zcore::{ex::Receipt, Block};
 
 // The interval to author the block in seconds
 const AUTHOR_INTERVAL: u64 = 3;
@@ -17,7 +23,9 @@ const ONE_SECOND: Duration = Duration::from_secs(1);
 ///
 /// - use the solana keyper as signer
 /// - The current node is always the leader.
-pub async fn start(mut runtime: Runtime<Development>) -> Result<()> {
+/// - The receipts are verified with the sync instance shared with the
+///   bundler, so the wallet is only opened once.
+pub async fn start(mut runtime: Runtime<Development>, sync: Arc<Mutex<Sync>>) -> Result<()> {
     let mut now = Instant::now();
     let authority = dev::load_authority()?;
     let ident = authority.pubkey().to_bytes();
@@ -29,6 +37,46 @@ pub async fn start(mut runtime: Runtime<Development>) -> Result<()> {
         }
 
         let mut block = runtime.author().await?;
+
+        // re-check the receipts against the target chains before voting,
+        // the invalid receipts are dropped, and the ones failed to verify
+        // are kept in the pool for the next blocks.
+        let verified = match verify(&runtime, &sync, &block).await {
+            Ok(verified) => verified,
+            Err(e) => {
+                runtime
+                    .pool
+                    .lock()
+                    .await
+                    .restore(mem::take(&mut block.extrinsic));
+                return Err(e);
+            }
+        };
+
+        let Verified {
+            valid,
+            invalid,
+            unverified,
+        } = verified;
+        if !invalid.is_empty() || !unverified.is_empty() {
+            tracing::warn!(
+                "Authoring block {} without {} invalid receipts dropped and {} receipts unverified",
+                block.header.slot,
+                invalid.len(),
+                unverified.len()
+            );
+            runtime.pool.lock().await.receipt.extend(unverified);
+            block.extrinsic.receipts = valid;
+            let extrinsic = mem::take(&mut block.extrinsic);
+            block = match runtime.build(extrinsic.clone()) {
+                Ok(block) => block,
+                Err(e) => {
+                    runtime.pool.lock().await.restore(extrinsic);
+                    return Err(e);
+                }
+            };
+        }
+
         let slot = block.header.slot;
         let hash = block.header.hash();
         let signature = authority.sign_message(&hash);
@@ -43,17 +91,98 @@ pub async fn start(mut runtime: Runtime<Development>) -> Result<()> {
             block.extrinsic.bridge.len(),
             block.extrinsic.receipts.len()
         );
-        runtime.import(&block)?;
-        runtime.hook.on_block_finalized(&block).await?;
+        // the block is validated before any write on import, the extrinsic
+        // of the rejected block goes back to the pool for the next slot
+        let bft = runtime.storage.state()?.bft;
+        if let Err(e) = runtime.import(&block) {
+            tracing::error!("Failed to import block {slot}: {e:?}");
+            runtime.pool.lock().await.restore(block.extrinsic);
+            now = Instant::now();
+            continue;
+        }
+
+        // NOTE: hook failures should never stop authoring
+        if let Err(e) = runtime.hook.on_block_imported(&block).await {
+            tracing::error!("Hook on_block_imported failed: {e:?}");
+        }
+
+        let imported = runtime.storage.state()?.bft;
+        if imported.validators != bft.validators || imported.threshold != bft.threshold {
+            if let Err(e) = runtime.hook.on_validators_changed(&imported).await {
+                tracing::error!("Hook on_validators_changed failed: {e:?}");
+            }
+        }
+
+        // the development node is the only validator, blocks are
+        // finalized once imported.
+        if let Err(e) = runtime.hook.on_block_finalized(&block).await {
+            tracing::error!("Hook on_block_finalized failed: {e:?}");
+        }
         now = Instant::now();
     }
 }
 
+/// The receipts of a block verified against the target chains
+#[derive(Default)]
+struct Verified {
+    /// The valid receipts
+    valid: Vec<Receipt>,
+
+    /// The receipts not matching the target chains
+    invalid: Vec<Receipt>,
+
+    /// The receipts failed to verify, e.g. the RPCs are unavailable
+    unverified: Vec<Receipt>,
+}
+
+/// Verify the receipts of the block against the target chains
+async fn verify(
+    runtime: &Runtime<Development>,
+    sync: &Arc<Mutex<Sync>>,
+    block: &Block,
+) -> Result<Verified> {
+    let mut verified = Verified::default();
+    let mut sync = sync.lock().await;
+    for receipt in &block.extrinsic.receipts {
+        let bridge = match block
+            .extrinsic
+            .bridges()
+            .find(|bridge| bridge.txid == receipt.anchor)
+        {
+            Some(bridge) => Some(bridge.clone()),
+            None => runtime.storage.bridge(&receipt.anchor)?,
+        };
+
+        let Some(bridge) = bridge else {
+            tracing::error!(
+                "Anchored bridge request of receipt {} not found",
+                bs58::encode(&receipt.txid).into_string()
+            );
+            verified.invalid.push(receipt.clone());
+            continue;
+        };
+
+        match sync.verify_receipt(receipt, &bridge).await {
+            Ok(()) => verified.valid.push(receipt.clone()),
+            Err(e) if e.is::<InvalidReceipt>() => {
+                tracing::error!("{e}");
+                verified.invalid.push(receipt.clone());
+            }
+            Err(e) => {
+                tracing::warn!("Failed to verify receipt: {e:?}");
+                verified.unverified.push(receipt.clone());
+            }
+        }
+    }
+
+    Ok(verified)
+}
+
 /// Spawn the authoring service
-pub fn spawn(runtime: Runtime<Development>) -> Result<()> {
+pub fn spawn(runtime: Runtime<Development>, sync: Arc<Mutex<Sync>>) -> Result<()> {
     tokio::spawn(async move {
         loop {
-            if let Err(e) = start(runtime.clone()).await {
+            if let Err(e) = start(runtime.clone(), sync.clone()).await {
                 tracing::error!("authoring service error:{e:?}, restarting in 5 seconds");
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
             }
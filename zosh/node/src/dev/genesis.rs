@@ -4,7 +4,21 @@ use anyhow::Result;
 use runtime::storage::Commit;
 use solana_signer::Signer;
 use sync::solana::dev;
-use zcore::{bft::Bft, state::key, Head, State};
+use zcore::{
+    bft::Bft,
+    state::{key, Fee, FeeSchedule},
+    Head, State,
+};
+
+/// The development fee of each bridge direction
+///
+/// The minimum covers the ZIP-317 fee of a zcash withdrawal and
+/// the rent of the new token accounts on solana.
+const DEV_FEE: Fee = Fee {
+    flat: 0,
+    bps: 10,
+    min: 20_000,
+};
 
 /// The genesis state for the development node
 pub fn commit() -> Result<Commit> {
@@ -21,9 +35,16 @@ pub fn commit() -> Result<Commit> {
         threshold: 1,
         series: vec![],
     };
+    let fee = FeeSchedule {
+        solana: DEV_FEE,
+        zcash: DEV_FEE,
+    };
 
     commit.insert(key::ACCUMULATOR_KEY, state.accumulator.to_vec());
     commit.insert(key::BFT_KEY, postcard::to_allocvec(&bft)?);
     commit.insert(key::PRESENT_KEY, postcard::to_allocvec(&head)?);
+    commit.insert(key::FEE_KEY, postcard::to_allocvec(&fee)?);
+    commit.insert(key::COLLECTED_KEY, postcard::to_allocvec(&state.collected)?);
+    commit.insert(key::LIMITS_KEY, postcard::to_allocvec(&state.limits)?);
     Ok(commit)
 }
@@ -1,7 +1,15 @@
 //! The development node implementation
 
-use crate::storage::Parity;
+use crate::{cmd::RpcArgs, storage::Parity};
+#[cfg(feature = "rpc")]
+use crate::{
+    metrics::{MetricsHook, NodeExporter},
+    rpc::{Rpc, RpcHook},
+};
 use anyhow::Result;
+pub use relay::Submission;
+#[cfg(feature = "rpc")]
+use rpc::server::SubscriptionManager;
 use runtime::{Config, Pool, Runtime, Storage};
 use std::{net::SocketAddr, sync::Arc};
 use sync::{config::CACHE_DIR, Sync};
@@ -23,8 +31,19 @@ pub struct Dev {
     /// The runtime
     pub runtime: Runtime<Development>,
 
+    /// The UI database
+    pub uidb: zoshui::Database,
+
     /// Stats broadcast sender for WebSocket subscriptions
     pub stats_tx: broadcast::Sender<zoshui::db::Stats>,
+
+    /// The RPC subscription manager
+    #[cfg(feature = "rpc")]
+    pub manager: SubscriptionManager,
+
+    /// The finalized blocks recorder of the metrics
+    #[cfg(feature = "rpc")]
+    pub metrics: MetricsHook,
 }
 
 impl Dev {
@@ -37,7 +56,13 @@ impl Dev {
         let (stats_tx, _) = broadcast::channel(16);
 
         let parity = Arc::new(Parity::try_from(CACHE_DIR.join("chain"))?);
-        let hook = zoshui::UIHook::new(uidb, stats_tx.clone());
+        let hook = zoshui::UIHook::new(uidb.clone(), stats_tx.clone());
+        #[cfg(feature = "rpc")]
+        let manager = SubscriptionManager::default();
+        #[cfg(feature = "rpc")]
+        let metrics = MetricsHook::default();
+        #[cfg(feature = "rpc")]
+        let hook = (hook, RpcHook::new(manager.clone()), metrics.clone());
         let runtime = Runtime::new(hook, parity.clone(), 1).await?;
         let pool = runtime.pool.clone();
         if parity.is_empty()? {
@@ -47,27 +72,72 @@ impl Dev {
             runtime,
             pool,
             parity,
+            uidb,
             stats_tx,
+            #[cfg(feature = "rpc")]
+            manager,
+            #[cfg(feature = "rpc")]
+            metrics,
         })
     }
 
     /// Start the development node
-    pub async fn start(self, address: SocketAddr) -> Result<()> {
+    ///
+    /// Serves the UI on `address` and the JSON-RPC on `rpc.rpc`.
+    pub async fn start(self, address: SocketAddr, rpc: RpcArgs) -> Result<()> {
+        if address.port() == rpc.rpc.port() {
+            anyhow::bail!(
+                "The UI and the RPC can not share the same port {}",
+                rpc.rpc.port()
+            );
+        }
+
         tracing::info!("Starting the development node");
         let Dev {
             parity,
             pool,
             runtime,
+            uidb,
             stats_tx,
+            #[cfg(feature = "rpc")]
+            manager,
+            #[cfg(feature = "rpc")]
+            metrics,
         } = self;
+
         let sync = Sync::load().await?;
-        zoshui::spawn(runtime.hook.db.clone(), address, stats_tx);
-        author::spawn(runtime)?;
+        let (submit, submissions) = mpsc::channel::<Submission>(512);
+        #[cfg(feature = "rpc")]
+        {
+            let server = Rpc::new(parity.clone(), pool.clone(), manager, submit);
+            let exporter = NodeExporter {
+                storage: parity.clone(),
+                pool: pool.clone(),
+                hook: metrics,
+                rpc: server.metrics.clone(),
+                sync: sync.metrics.clone(),
+            };
+            server.spawn(rpc.rpc, (&rpc).into(), exporter)?;
+        }
+        #[cfg(not(feature = "rpc"))]
+        {
+            drop(submit);
+            tracing::warn!(
+                "Built without the rpc feature, skipping the RPC on {}",
+                rpc.rpc
+            );
+        }
+
+        // the collector, the bundler and the author share one wallet
+        let sync = Arc::new(Mutex::new(sync));
+        zoshui::spawn(uidb, address, stats_tx);
+        let hook = runtime.hook.clone();
+        author::spawn(runtime, sync.clone())?;
 
         // spawn the sync service
         let (tx, rx) = mpsc::channel::<Bridge>(512);
-        sync.spawn(tx);
-        relay::spawn(parity, pool, rx).await?;
+        Sync::spawn(sync.clone(), tx).await?;
+        relay::spawn(parity, pool, sync, hook, rx, submissions).await?;
         let _ = tokio::signal::ctrl_c().await;
         Ok(())
     }
@@ -78,6 +148,9 @@ impl Dev {
 pub struct Development;
 
 impl Config for Development {
+    #[cfg(feature = "rpc")]
+    type Hook = (zoshui::UIHook, RpcHook, MetricsHook);
+    #[cfg(not(feature = "rpc"))]
     type Hook = zoshui::UIHook;
     type Storage = Arc<Parity>;
 }
@@ -1,16 +1,19 @@
 //! The development node services
 
-use crate::storage::Parity;
+use crate::{dev::Development, storage::Parity};
 use anyhow::Result;
-use runtime::{Pool, Storage};
+use runtime::{Config, Hook, Pool, Storage};
 use std::{
     mem,
     sync::Arc,
     time::{Duration, Instant},
 };
 use sync::{zcash::Network, ChainFormatEncoder, Sync};
-use tokio::sync::{mpsc, Mutex};
-use zcore::{ex::Bridge, registry::Chain};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use zcore::{
+    ex::{Bridge, BridgeBundle},
+    registry::Chain,
+};
 
 // The interval to bundle the transactions in seconds
 const BUNDLE_INTERVAL: u64 = 3;
@@ -18,66 +21,116 @@ const BUNDLE_INTERVAL: u64 = 3;
 /// One second
 const ONE_SECOND: Duration = Duration::from_secs(1);
 
+/// A bridge request submitted by an external collector
+pub struct Submission {
+    /// The submitted bridge request
+    pub bridge: Bridge,
+
+    /// The reply with the queued bridge request
+    pub reply: oneshot::Sender<Result<Bridge>>,
+}
+
 /// Start the relay service
 pub async fn spawn(
     parity: Arc<Parity>,
     pool: Arc<Mutex<Pool>>,
+    sync: Arc<Mutex<Sync>>,
+    hook: <Development as Config>::Hook,
     rx: mpsc::Receiver<Bridge>,
+    submissions: mpsc::Receiver<Submission>,
 ) -> Result<()> {
-    let sync = Arc::new(Mutex::new(Sync::load().await?));
     let bridges = Arc::new(Mutex::new(Vec::new()));
 
     // spawn the bundler service
+    let parity2 = parity.clone();
     let sync2 = sync.clone();
     let bridges2 = bridges.clone();
+    let hook2 = hook.clone();
     tokio::spawn(async move {
         loop {
-            if let Err(e) = bundler(sync2.clone(), bridges2.clone(), pool.clone()).await {
+            if let Err(e) = bundler(&parity2, &sync2, &bridges2, &pool, &hook2).await {
                 tracing::error!("Bundler error: {e:?}, retrying in 5 seconds");
                 tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
             }
         }
     });
 
-    tokio::spawn(async move { collector(parity, rx, sync.clone(), bridges.clone()).await });
+    tokio::spawn(async move {
+        collector(parity, rx, submissions, sync.clone(), bridges.clone(), hook).await
+    });
     Ok(())
 }
 
 async fn collector(
     parity: Arc<Parity>,
     mut rx: mpsc::Receiver<Bridge>,
+    mut submissions: mpsc::Receiver<Submission>,
     sync: Arc<Mutex<Sync>>,
     bridges: Arc<Mutex<Vec<Bridge>>>,
+    hook: <Development as Config>::Hook,
 ) -> Result<()> {
-    while let Some(bridge) = rx.recv().await {
-        if let Err(e) = collector_internal(&parity, &sync, &bridges, bridge).await {
-            tracing::error!("{e:?}");
+    loop {
+        tokio::select! {
+            Some(bridge) = rx.recv() => {
+                if let Err(e) = collector_internal(&parity, &sync, &bridges, &hook, bridge).await {
+                    tracing::error!("{e:?}");
+                }
+            }
+            Some(Submission { bridge, reply }) = submissions.recv() => {
+                let _ = reply.send(submit(&parity, &sync, &bridges, &hook, bridge).await);
+            }
+            else => break,
         }
-
-        continue;
     }
     Ok(())
 }
 
-async fn collector_internal(
+/// Queue the bridge request submitted by an external collector
+async fn submit(
     parity: &Arc<Parity>,
     sync: &Arc<Mutex<Sync>>,
     bridges: &Arc<Mutex<Vec<Bridge>>>,
+    hook: &<Development as Config>::Hook,
     bridge: Bridge,
-) -> Result<()> {
+) -> Result<Bridge> {
+    let txid = bs58::encode(&bridge.txid).into_string();
+    collector_internal(parity, sync, bridges, hook, bridge)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Bridge request {txid} is already processed"))
+}
+
+async fn collector_internal(
+    parity: &Arc<Parity>,
+    sync: &Arc<Mutex<Sync>>,
+    bridges: &Arc<Mutex<Vec<Bridge>>>,
+    hook: &<Development as Config>::Hook,
+    mut bridge: Bridge,
+) -> Result<Option<Bridge>> {
     // skip if the transaction is already processed
     if parity.exists(&bridge.txid)? {
-        return Ok(());
+        return Ok(None);
     }
 
+    // charge the bridge fee from the fee schedule of the chain state
+    bridge.fee = parity
+        .state()?
+        .fee
+        .get(bridge.target)
+        .charge(bridge.amount)?;
+
     // validate the bridge request
     //
     // TODO: in production we should do this in parallel.
-    sync.lock().await.validate_bridge(&bridge).await?;
+    if let Err(e) = sync.lock().await.validate_bridge(&bridge).await {
+        if let Err(e) = hook.on_bridge_failed(&bridge, &e.to_string()).await {
+            tracing::error!("Hook on_bridge_failed failed: {e:?}");
+        }
+        return Err(e);
+    }
 
     // print the bridge request details
     tracing::info!(
-        "Received bridge request: from {:?} to {:?}({}) with amount {}, txid={}",
+        "Received bridge request: from {:?} to {:?}({}) with amount {} (fee {}), txid={}",
         bridge.source,
         bridge.target,
         match bridge.target {
@@ -91,6 +144,7 @@ async fn collector_internal(
             ),
         },
         bridge.amount,
+        bridge.fee,
         match bridge.source {
             Chain::Solana => bridge.txid.solana_signature()?.to_string(),
             Chain::Zcash => bridge.txid.zcash_txid()?.to_string(),
@@ -99,14 +153,19 @@ async fn collector_internal(
 
     // Do the validation of the bridge request, insert to the queue
     // if it is valid.
-    bridges.lock().await.push(bridge);
-    Ok(())
+    if let Err(e) = hook.on_bridge_queued(&bridge).await {
+        tracing::error!("Hook on_bridge_queued failed: {e:?}");
+    }
+    bridges.lock().await.push(bridge.clone());
+    Ok(Some(bridge))
 }
 
 async fn bundler(
-    sync: Arc<Mutex<Sync>>,
-    bridges: Arc<Mutex<Vec<Bridge>>>,
-    pool: Arc<Mutex<Pool>>,
+    parity: &Arc<Parity>,
+    sync: &Arc<Mutex<Sync>>,
+    bridges: &Arc<Mutex<Vec<Bridge>>>,
+    pool: &Arc<Mutex<Pool>>,
+    hook: &<Development as Config>::Hook,
 ) -> Result<()> {
     let mut now = Instant::now();
     loop {
@@ -115,20 +174,118 @@ async fn bundler(
             continue;
         }
 
-        let mut bridges = bridges.lock().await;
-        if bridges.is_empty() {
-            continue;
+        let bridges = mem::take(&mut *bridges.lock().await);
+        let mut sync = sync.lock().await;
+
+        // rebuild and re-sign the expired zcash transactions first
+        let limits = parity.state()?.limits;
+        let mut bundles = sync.rebuild(&limits).await.unwrap_or_else(|e| {
+            tracing::error!("Failed to rebuild the expired zcash transactions: {e:?}");
+            Vec::new()
+        });
+        if !bridges.is_empty() || !sync.unresolved.is_empty() {
+            // NOTE: the bundles are signed by the development MPC directly
+            bundles.extend(sync.bundle(bridges, &limits).await?);
+            for bridge in mem::take(&mut sync.reorged) {
+                if let Err(e) = hook
+                    .on_bridge_failed(&bridge, "The source deposit is reorged")
+                    .await
+                {
+                    tracing::error!("Hook on_bridge_failed failed: {e:?}");
+                }
+            }
         }
 
-        let mut sync = sync.lock().await;
-        let (bundles, receipts) = sync.bundle(mem::take(&mut bridges)).await?;
-        let mut pool = pool.lock().await;
-        if !bundles.is_empty() {
-            pool.bridge.dev_pack(bundles)?;
+        for bundle in &bundles {
+            if let Err(e) = hook.on_bundle_signed(bundle).await {
+                tracing::error!("Hook on_bundle_signed failed: {e:?}");
+            }
+        }
+
+        // the rebuilt bundles pay the included bridge requests again, only
+        // their receipts reach the chain
+        let mut pooled = Vec::new();
+        for mut bundle in bundles {
+            strip(parity, &mut bundle)?;
+            if !bundle.bridge.is_empty() {
+                pooled.push(bundle);
+            }
         }
+        let bundles = pooled;
+
+        // the bundles exceeding the block limits can never be packed, and
+        // the ones spending the notes of the pooled bundles never land
+        let rejected = if bundles.is_empty() {
+            Vec::new()
+        } else {
+            pool.lock()
+                .await
+                .bridge
+                .dev_pack(bundles.clone(), &limits)?
+        };
+
+        for (bundle, reason) in &rejected {
+            tracing::error!(
+                "Rejected bundle of {} bridges: {reason}",
+                bundle.bridge.len()
+            );
+            sync.untrack(bundle);
+            for bridge in &bundle.bridge {
+                if let Err(e) = hook.on_bridge_failed(bridge, reason).await {
+                    tracing::error!("Hook on_bridge_failed failed: {e:?}");
+                }
+            }
+        }
+
+        let rejected = rejected
+            .iter()
+            .map(|(bundle, _)| bundle.hash())
+            .collect::<Result<Vec<_>>>()?;
+        for bundle in &bundles {
+            if rejected.contains(&bundle.hash()?) {
+                continue;
+            }
+
+            if let Err(e) = hook.on_bundle_queued(bundle).await {
+                tracing::error!("Hook on_bundle_queued failed: {e:?}");
+            }
+        }
+
+        // generate the receipts of the transactions reached the confirmation depth
+        let receipts = sync.confirm().await?;
+        for (bridge, reason) in mem::take(&mut sync.flagged) {
+            if let Err(e) = hook.on_bridge_failed(&bridge, &reason).await {
+                tracing::error!("Hook on_bridge_failed failed: {e:?}");
+            }
+        }
+
         if !receipts.is_empty() {
-            pool.receipt.extend_from_slice(&receipts);
+            pool.lock().await.receipt.extend_from_slice(&receipts);
+        }
+
+        for receipt in &receipts {
+            if let Err(e) = hook.on_receipt_produced(receipt).await {
+                tracing::error!("Hook on_receipt_produced failed: {e:?}");
+            }
         }
         now = Instant::now();
     }
 }
+
+/// Drop the bridge requests already included in the chain from the bundle
+fn strip(parity: &Parity, bundle: &mut BridgeBundle) -> Result<()> {
+    let mut bridges = Vec::new();
+    for bridge in mem::take(&mut bundle.bridge) {
+        if parity.exists(&bridge.txid)? {
+            tracing::info!(
+                "Bridge request {} is already included, skip pooling",
+                bs58::encode(&bridge.txid).into_string()
+            );
+            continue;
+        }
+        bridges.push(bridge);
+    }
+
+    bundle.bridge = bridges;
+    Ok(())
+}
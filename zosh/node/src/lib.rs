@@ -2,5 +2,6 @@
 
 pub mod cmd;
 pub mod dev;
+pub mod metrics;
 pub mod rpc;
 pub mod storage;
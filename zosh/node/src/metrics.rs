@@ -0,0 +1,209 @@
+//! Prometheus metrics of the node
+#![cfg(feature = "rpc")]
+
+use anyhow::Result;
+use rpc::server::{middleware::Metrics as RpcMetrics, Exporter, Exposition};
+use runtime::{Hook, Pool, Storage};
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Instant,
+};
+use sync::metrics::Metrics as SyncMetrics;
+use tokio::sync::Mutex;
+use zcore::Block;
+
+/// The hook recording the time of the finalized blocks
+#[derive(Clone)]
+pub struct MetricsHook {
+    /// The time of the latest finalized block, or the start of the node
+    pub finalized: Arc<std::sync::Mutex<Instant>>,
+}
+
+impl Default for MetricsHook {
+    fn default() -> Self {
+        Self {
+            finalized: Arc::new(std::sync::Mutex::new(Instant::now())),
+        }
+    }
+}
+
+impl Hook for MetricsHook {
+    async fn on_block_finalized(&self, _block: &Block) -> Result<()> {
+        *self.finalized.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        Ok(())
+    }
+}
+
+/// The prometheus exporter of the node
+#[derive(Clone)]
+pub struct NodeExporter<S: Storage> {
+    /// The storage
+    pub storage: Arc<S>,
+
+    /// The mempool
+    pub pool: Arc<Mutex<Pool>>,
+
+    /// The finalized blocks recorder
+    pub hook: MetricsHook,
+
+    /// The RPC request metrics
+    pub rpc: RpcMetrics,
+
+    /// The sync metrics
+    pub sync: Arc<SyncMetrics>,
+}
+
+impl<S: Storage> Exporter for NodeExporter<S> {
+    async fn export(&self) -> Result<String> {
+        let mut exposition = Exposition::default();
+        let slot = self.storage.state()?.present.slot;
+        let elapsed = self
+            .hook
+            .finalized
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .elapsed();
+        exposition
+            .gauge("zosh_slot", "The slot of the latest block", slot as f64)
+            .gauge(
+                "zosh_seconds_since_last_block",
+                "The seconds since the latest finalized block",
+                elapsed.as_secs_f64(),
+            );
+
+        let (in_progress, completed, receipts) = {
+            let pool = self.pool.lock().await;
+            (
+                pool.bridge.in_progress(),
+                pool.bridge.completed(),
+                pool.receipt.len(),
+            )
+        };
+        exposition
+            .family("zosh_pool_bundles", "gauge", "The bundles in the mempool")
+            .sample(
+                "zosh_pool_bundles",
+                &[("state", "in_progress")],
+                in_progress as f64,
+            )
+            .sample(
+                "zosh_pool_bundles",
+                &[("state", "completed")],
+                completed as f64,
+            )
+            .gauge(
+                "zosh_pool_receipts",
+                "The receipts in the mempool",
+                receipts as f64,
+            );
+
+        let sync = &self.sync;
+        exposition
+            .gauge(
+                "zosh_sync_unresolved",
+                "The bridge requests waiting for bundling again",
+                sync.unresolved.load(Ordering::Relaxed) as f64,
+            )
+            .gauge(
+                "zosh_zcash_sync_height",
+                "The height the zcash wallet is scanned to",
+                sync.zcash_height.load(Ordering::Relaxed) as f64,
+            )
+            .gauge(
+                "zosh_zcash_tip_height",
+                "The height of the zcash chain tip",
+                sync.zcash_tip.load(Ordering::Relaxed) as f64,
+            )
+            .gauge(
+                "zosh_zcash_blacklisted_notes",
+                "The blacklisted zcash notes",
+                sync.blacklisted.load(Ordering::Relaxed) as f64,
+            )
+            .family(
+                "zosh_zcash_reorgs_total",
+                "counter",
+                "The reorgs detected by the zcash wallet",
+            )
+            .sample(
+                "zosh_zcash_reorgs_total",
+                &[],
+                sync.zcash_reorgs.load(Ordering::Relaxed) as f64,
+            )
+            .family(
+                "zosh_solana_reconnects_total",
+                "counter",
+                "The reconnects of the solana subscription",
+            )
+            .sample(
+                "zosh_solana_reconnects_total",
+                &[],
+                sync.solana_reconnects.load(Ordering::Relaxed) as f64,
+            );
+
+        let bundles = sync.bundles();
+        exposition.family(
+            "zosh_bundle_send_seconds",
+            "histogram",
+            "The latency of signing and sending the bundles",
+        );
+        for (chain, metrics) in &bundles {
+            let chain = format!("{chain:?}");
+            exposition.histogram(
+                "zosh_bundle_send_seconds",
+                &[("chain", &chain)],
+                &metrics.latency,
+            );
+        }
+
+        exposition.family(
+            "zosh_bundles_sent_total",
+            "counter",
+            "The bundles sent to the target chains",
+        );
+        for (chain, metrics) in &bundles {
+            let chain = format!("{chain:?}");
+            exposition.sample(
+                "zosh_bundles_sent_total",
+                &[("chain", &chain)],
+                metrics.sent as f64,
+            );
+        }
+
+        exposition.family(
+            "zosh_bundles_expired_total",
+            "counter",
+            "The sent bundles expired before mined",
+        );
+        for (chain, metrics) in &bundles {
+            let chain = format!("{chain:?}");
+            exposition.sample(
+                "zosh_bundles_expired_total",
+                &[("chain", &chain)],
+                metrics.expired as f64,
+            );
+        }
+
+        exposition.family(
+            "zosh_bundle_failures_total",
+            "counter",
+            "The failed bundles by the stage, bundle or send",
+        );
+        for (chain, metrics) in &bundles {
+            let chain = format!("{chain:?}");
+            exposition
+                .sample(
+                    "zosh_bundle_failures_total",
+                    &[("chain", &chain), ("stage", "bundle")],
+                    metrics.bundle_failures as f64,
+                )
+                .sample(
+                    "zosh_bundle_failures_total",
+                    &[("chain", &chain), ("stage", "send")],
+                    metrics.send_failures as f64,
+                );
+        }
+
+        self.rpc.export(&mut exposition);
+        Ok(exposition.finish())
+    }
+}
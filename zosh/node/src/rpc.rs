@@ -1,18 +1,27 @@
 //! RPC implementation for the zosh node
 #![cfg(feature = "rpc")]
 
+use crate::{cmd::RpcArgs, dev::Submission};
 use anyhow::Result;
 use async_trait::async_trait;
 use rpc::{
     server::{
-        middleware, ErrorCode, ErrorObjectOwned, PendingSubscriptionSink, RpcServiceBuilder,
-        Server, SubscriptionManager, SubscriptionResult,
+        self,
+        middleware::{Metrics, Policy},
+        ErrorCode, ErrorObjectOwned, Exporter, PendingSubscriptionSink, SubscriptionManager,
+        SubscriptionResult,
     },
-    ApiServer,
+    types::decode_txid,
+    ApiServer, BlockId, BlockInterface, BridgeFilter, Encoding, PoolStatus, RpcBridge, RpcHeader,
+    RpcInclusionProof, RpcReceipt, SubmitAck, TransactionStatus, Validators,
 };
-use runtime::Storage;
+use runtime::{Hook, Pool, Storage};
 use std::{net::SocketAddr, sync::Arc};
-use zcore::State;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use zcore::{
+    ex::{Bridge, BridgeBundle},
+    Block, Hash, InclusionProof, State,
+};
 
 /// The response type
 pub type Response<T> = core::result::Result<T, ErrorObjectOwned>;
@@ -23,36 +32,66 @@ pub struct Rpc<S: Storage> {
     /// The storage
     pub storage: Arc<S>,
 
+    /// The mempool
+    pub pool: Arc<Mutex<Pool>>,
+
     /// the subscription manager
     pub manager: SubscriptionManager,
+
+    /// The submission queue of the relay
+    pub submit: mpsc::Sender<Submission>,
+
+    /// The request metrics
+    pub metrics: Metrics,
 }
 
 impl<S: Storage> Rpc<S> {
     /// Create a new RPC instance
-    pub fn new(storage: Arc<S>, manager: SubscriptionManager) -> Self {
-        Self { storage, manager }
-    }
-
-    /// Start the RPC server
-    pub async fn start(self, addr: SocketAddr) -> Result<()> {
-        let rpc_middleware = RpcServiceBuilder::new().layer_fn(middleware::Logger);
-        let server = Server::builder()
-            .set_rpc_middleware(rpc_middleware)
-            .build(addr)
-            .await?;
-
-        // start the rpc server
-        let addr = server.local_addr()?;
-        tracing::info!("Listening RPC on {}", addr);
-        server.start(self.into_rpc()).stopped().await;
-        Ok(())
+    pub fn new(
+        storage: Arc<S>,
+        pool: Arc<Mutex<Pool>>,
+        manager: SubscriptionManager,
+        submit: mpsc::Sender<Submission>,
+    ) -> Self {
+        Self {
+            storage,
+            pool,
+            manager,
+            submit,
+            metrics: Metrics::default(),
+        }
+    }
+
+    /// Get the block by its hash
+    fn block(&self, hash: &Hash) -> Response<Option<Block>> {
+        self.storage.block(hash).map_err(internal)
+    }
+
+    /// Get the block by its slot
+    fn block_at(&self, slot: u32) -> Response<Option<Block>> {
+        match self.storage.block_hash(slot).map_err(internal)? {
+            Some(hash) => self.block(&hash),
+            None => Ok(None),
+        }
+    }
+
+    /// Start the RPC server, serving the metrics of the exporter
+    pub async fn start(
+        self,
+        addr: SocketAddr,
+        policy: Policy,
+        exporter: impl Exporter,
+    ) -> Result<()> {
+        let metrics = self.metrics.clone();
+        server::serve(addr, self.into_rpc(), policy, metrics, exporter).await
     }
 
     /// Spawn the RPC server
-    pub fn spawn(self, addr: SocketAddr) -> Result<()> {
+    pub fn spawn(self, addr: SocketAddr, policy: Policy, exporter: impl Exporter) -> Result<()> {
         tokio::spawn(async move {
             loop {
-                if let Err(e) = self.clone().start(addr).await {
+                let exporter = exporter.clone();
+                if let Err(e) = self.clone().start(addr, policy.clone(), exporter).await {
                     tracing::error!("rpc service error:{e:?}, restarting in 5 seconds");
                     tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
                 }
@@ -62,23 +101,233 @@ impl<S: Storage> Rpc<S> {
     }
 }
 
+/// The hook dispatching the finalized blocks to the RPC subscribers
+#[derive(Clone, Default)]
+pub struct RpcHook {
+    /// The subscription manager
+    pub manager: SubscriptionManager,
+}
+
+impl RpcHook {
+    /// Create a new RPC hook
+    pub fn new(manager: SubscriptionManager) -> Self {
+        Self { manager }
+    }
+}
+
+impl Hook for RpcHook {
+    /// NOTE: a failed dispatch is logged without skipping the others
+    async fn on_block_finalized(&self, block: &Block) -> Result<()> {
+        if let Err(e) = self.manager.dispatch_block(block).await {
+            tracing::error!("Failed to dispatch block: {e:?}");
+        }
+
+        if let Err(e) = self.manager.dispatch_bridges(block).await {
+            tracing::error!("Failed to dispatch bridges: {e:?}");
+        }
+
+        let slot = block.header.slot;
+        let mut statuses = Vec::new();
+        for bridge in block.extrinsic.bridges() {
+            statuses.push((&bridge.txid, TransactionStatus::Included { slot }));
+        }
+
+        for receipt in &block.extrinsic.receipts {
+            let status = TransactionStatus::Confirmed {
+                slot,
+                receipt: receipt.txid.clone(),
+            };
+            statuses.push((&receipt.anchor, status.clone()));
+            statuses.push((&receipt.txid, status));
+        }
+
+        for (txid, status) in statuses {
+            if let Err(e) = self.manager.dispatch_transaction(txid, status).await {
+                tracing::error!("Failed to dispatch transaction status: {e:?}");
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_bridge_queued(&self, bridge: &Bridge) -> Result<()> {
+        self.manager
+            .dispatch_transaction(&bridge.txid, TransactionStatus::Queued)
+            .await
+    }
+
+    async fn on_bridge_failed(&self, bridge: &Bridge, reason: &str) -> Result<()> {
+        let status = TransactionStatus::Failed {
+            reason: reason.to_string(),
+        };
+        self.manager
+            .dispatch_transaction(&bridge.txid, status)
+            .await
+    }
+
+    async fn on_bundle_queued(&self, bundle: &BridgeBundle) -> Result<()> {
+        let hash = bundle.hash()?.to_vec();
+        for bridge in &bundle.bridge {
+            let status = TransactionStatus::Bundled {
+                bundle: hash.clone(),
+            };
+            self.manager
+                .dispatch_transaction(&bridge.txid, status)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl<S: Storage> ApiServer for Rpc<S> {
     /// Get the chain info
     async fn chain(&self) -> Response<State> {
-        self.storage.state().map_err(|e| {
-            ErrorObjectOwned::owned(
-                ErrorCode::InternalError.code(),
-                e.to_string(),
-                Option::<()>::None,
-            )
+        self.storage.state().map_err(internal)
+    }
+
+    /// Get the block by its hash
+    async fn block_by_hash(
+        &self,
+        hash: String,
+        encoding: Option<Encoding>,
+    ) -> Response<Option<BlockInterface>> {
+        let block = self.block(&decode_hash(&hash)?)?;
+        encode(block, encoding)
+    }
+
+    /// Get the block by its slot
+    async fn block_by_slot(
+        &self,
+        slot: u32,
+        encoding: Option<Encoding>,
+    ) -> Response<Option<BlockInterface>> {
+        encode(self.block_at(slot)?, encoding)
+    }
+
+    /// Get the block header by its slot or hash
+    async fn header(&self, id: BlockId) -> Response<Option<RpcHeader>> {
+        let block = match id {
+            BlockId::Slot(slot) => self.block_at(slot)?,
+            BlockId::Hash(hash) => self.block(&decode_hash(&hash)?)?,
+        };
+        Ok(block.map(|block| RpcHeader::from(&block.header)))
+    }
+
+    /// Get the bridge request by its source txid
+    async fn bridge(&self, txid: String) -> Response<Option<RpcBridge>> {
+        let txid = decode_txid(&txid).map_err(invalid)?;
+        let bridge = self.storage.bridge(&txid).map_err(internal)?;
+        Ok(bridge.as_ref().map(Into::into))
+    }
+
+    /// Get the receipt by the source txid of its bridge request
+    async fn receipt(&self, anchor: String) -> Response<Option<RpcReceipt>> {
+        let anchor = decode_txid(&anchor).map_err(invalid)?;
+        let receipt = self.storage.receipt(&anchor).map_err(internal)?;
+        Ok(receipt.as_ref().map(Into::into))
+    }
+
+    /// Get the inclusion proof of a txid in its finalized block
+    ///
+    /// The receipts are keyed by their txids followed by their anchors.
+    async fn inclusion_proof(
+        &self,
+        txid: String,
+        anchor: Option<String>,
+    ) -> Response<Option<RpcInclusionProof>> {
+        let mut txid = decode_txid(&txid).map_err(invalid)?;
+        if let Some(anchor) = anchor {
+            txid.extend(decode_txid(&anchor).map_err(invalid)?);
+        }
+
+        let Some(slot) = self.storage.tx_slot(&txid).map_err(internal)? else {
+            return Ok(None);
+        };
+
+        let block = self
+            .block_at(slot)?
+            .ok_or_else(|| internal(format!("Missing block at slot {slot}")))?;
+        let proof = InclusionProof::new(&block, &txid).map_err(internal)?;
+        Ok(Some(RpcInclusionProof::from(&proof)))
+    }
+
+    /// Get the status of the mempool
+    async fn pool_status(&self) -> Response<PoolStatus> {
+        let pool = self.pool.lock().await;
+        Ok(PoolStatus {
+            in_progress: pool.bridge.in_progress(),
+            completed: pool.bridge.completed(),
+            receipts: pool.receipt.len(),
+        })
+    }
+
+    /// Get the current validator set
+    async fn validators(&self) -> Response<Validators> {
+        let state = self.storage.state().map_err(internal)?;
+        Ok(Validators::from(&state.bft))
+    }
+
+    /// Submit a bridge request to the relay
+    ///
+    /// The fee must be the one charged by the fee schedule, the relay
+    /// charges the scheduled fee anyway.
+    async fn submit_bridge(&self, bridge: RpcBridge) -> Response<SubmitAck> {
+        let bridge = Bridge::try_from(&bridge).map_err(invalid)?;
+        if self.storage.exists(&bridge.txid).map_err(internal)? {
+            return Err(invalid("Bridge request is already processed"));
+        }
+
+        let state = self.storage.state().map_err(internal)?;
+        let fee = state
+            .fee
+            .get(bridge.target)
+            .charge(bridge.amount)
+            .map_err(invalid)?;
+        if bridge.fee != fee {
+            return Err(invalid(format!(
+                "Invalid bridge fee: expected={fee}, got={}",
+                bridge.fee
+            )));
+        }
+
+        let (reply, queued) = oneshot::channel();
+        self.submit
+            .send(Submission { bridge, reply })
+            .await
+            .map_err(|_| internal("The relay is not running"))?;
+
+        let bridge = queued
+            .await
+            .map_err(|_| internal("The relay dropped the submission"))?
+            .map_err(invalid)?;
+        Ok(SubmitAck {
+            id: bs58::encode(bridge.hash().map_err(internal)?).into_string(),
+            txid: rpc::types::encode_txid(&bridge.txid),
+            fee: bridge.fee,
         })
     }
 
     /// Subscribe to new blocks
-    async fn subscribe_block(&self, sink: PendingSubscriptionSink) -> SubscriptionResult {
+    async fn subscribe_block(
+        &self,
+        sink: PendingSubscriptionSink,
+        encoding: Option<Encoding>,
+    ) -> SubscriptionResult {
+        let sink = sink.accept().await?;
+        self.manager
+            .subscribe_block(encoding.unwrap_or_default(), sink)
+            .await;
+        Ok(())
+    }
+
+    /// Subscribe to the finalized bridge requests matching the filter
+    async fn subscribe_bridges(
+        &self,
+        sink: PendingSubscriptionSink,
+        filter: BridgeFilter,
+    ) -> SubscriptionResult {
         let sink = sink.accept().await?;
-        self.manager.block_sub.lock().await.push(sink);
+        self.manager.subscribe_bridges(filter, sink).await;
         Ok(())
     }
 
@@ -86,10 +335,197 @@ impl<S: Storage> ApiServer for Rpc<S> {
     async fn subscribe_transaction(
         &self,
         sink: PendingSubscriptionSink,
-        txid: Vec<u8>,
+        txid: String,
     ) -> SubscriptionResult {
+        let txid = decode_txid(&txid)?;
         let sink = sink.accept().await?;
-        self.manager.transaction_sub.lock().await.push((txid, sink));
+        self.manager.subscribe_transaction(txid, sink).await?;
         Ok(())
     }
 }
+
+/// Encode the block, in json by default
+fn encode(block: Option<Block>, encoding: Option<Encoding>) -> Response<Option<BlockInterface>> {
+    block
+        .map(|block| BlockInterface::new(&block, encoding.unwrap_or_default()))
+        .transpose()
+        .map_err(internal)
+}
+
+impl From<&RpcArgs> for Policy {
+    fn from(args: &RpcArgs) -> Self {
+        let mut policy = Policy {
+            token: args.rpc_token.clone(),
+            ip_rate: args.rpc_ip_rate,
+            max_subscriptions: args.rpc_max_subscriptions,
+            ..Default::default()
+        };
+        policy
+            .method_rates
+            .insert("zosh_submitBridge".into(), args.rpc_submit_rate);
+        policy
+    }
+}
+
+/// Decode the base58 block hash
+fn decode_hash(hash: &str) -> Response<Hash> {
+    bs58::decode(hash)
+        .into_vec()
+        .map_err(invalid)?
+        .try_into()
+        .map_err(|_| invalid(format!("Invalid block hash {hash}")))
+}
+
+/// The internal error
+fn internal(e: impl ToString) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(
+        ErrorCode::InternalError.code(),
+        e.to_string(),
+        Option::<()>::None,
+    )
+}
+
+/// The invalid params error
+fn invalid(e: impl ToString) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(
+        ErrorCode::InvalidParams.code(),
+        e.to_string(),
+        Option::<()>::None,
+    )
+}
+
+/// The block with a bridge request and its receipt for the tests
+#[cfg(test)]
+fn test_block() -> Result<Block> {
+    use zcore::{ex::Receipt, registry::Chain, registry::Coin};
+
+    let bridge = Bridge {
+        coin: Coin::Zec,
+        recipient: vec![1; 32],
+        amount: 100_000,
+        fee: 10_000,
+        source: Chain::Zcash,
+        target: Chain::Solana,
+        txid: vec![2; 32],
+    };
+    let bundle = BridgeBundle {
+        target: Chain::Solana,
+        bridge: vec![bridge.clone()],
+        data: Vec::new(),
+        signatures: Vec::new(),
+    };
+
+    let mut block = Block::default();
+    block.header.version = zcore::HEADER_VERSION;
+    block.header.slot = 1;
+    block.extrinsic.bridge.insert(bundle.hash()?, bundle);
+    block.extrinsic.receipts.push(Receipt {
+        anchor: bridge.txid.clone(),
+        coin: bridge.coin,
+        amount: bridge.net(),
+        txid: vec![3; 64],
+        source: bridge.source,
+        target: bridge.target,
+    });
+    Ok(block)
+}
+
+#[tokio::test]
+async fn test_rpc_hook_status() -> Result<()> {
+    let block = test_block()?;
+    let hook = RpcHook::default();
+    let bridge = block.extrinsic.bridges().next().cloned().unwrap();
+    let bundle = block.extrinsic.bridge.values().next().unwrap();
+    let status = |txid: Vec<u8>| {
+        let manager = hook.manager.clone();
+        async move { manager.status.lock().await.get(&txid).cloned() }
+    };
+
+    hook.on_bridge_queued(&bridge).await?;
+    assert_eq!(
+        status(bridge.txid.clone()).await,
+        Some(TransactionStatus::Queued)
+    );
+
+    hook.on_bundle_queued(bundle).await?;
+    assert_eq!(
+        status(bridge.txid.clone()).await,
+        Some(TransactionStatus::Bundled {
+            bundle: bundle.hash()?.to_vec()
+        })
+    );
+
+    // the receipt confirms both the anchor and the target txid
+    hook.on_block_finalized(&block).await?;
+    let confirmed = TransactionStatus::Confirmed {
+        slot: 1,
+        receipt: vec![3; 64],
+    };
+    assert_eq!(status(bridge.txid.clone()).await, Some(confirmed.clone()));
+    assert_eq!(status(vec![3; 64]).await, Some(confirmed));
+
+    hook.on_bridge_failed(&bridge, "reorged").await?;
+    assert_eq!(
+        status(bridge.txid).await,
+        Some(TransactionStatus::Failed {
+            reason: "reorged".into()
+        })
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rpc_queries() -> Result<()> {
+    use crate::storage::Parity;
+    use rpc::types::encode_txid;
+
+    let path = std::env::temp_dir().join(format!("zosh-rpc-{}", std::process::id()));
+    let storage = Arc::new(Parity::try_from(path.clone())?);
+    let block = test_block()?;
+    let bridge = block.extrinsic.bridges().next().cloned().unwrap();
+    let receipt = block.extrinsic.receipts[0].clone();
+    storage.set_block(&block)?;
+    storage.set_txs(1, block.extrinsic.txs())?;
+    storage.set_bridges(vec![bridge.clone()])?;
+    storage.set_receipts(vec![receipt.clone()])?;
+
+    let (submit, _submissions) = mpsc::channel(1);
+    let rpc = Rpc::new(
+        storage,
+        Default::default(),
+        SubscriptionManager::default(),
+        submit,
+    );
+
+    let hash = bs58::encode(block.header.hash()).into_string();
+    let header = rpc.header(BlockId::Slot(1)).await?.unwrap();
+    assert_eq!(header.hash, hash);
+    assert!(rpc.header(BlockId::Slot(2)).await?.is_none());
+    let Some(BlockInterface::Json(json)) = rpc.block_by_hash(hash, None).await? else {
+        anyhow::bail!("Missing json block");
+    };
+    assert_eq!(json.header.slot, 1);
+
+    let found = rpc.bridge(encode_txid(&bridge.txid)).await?.unwrap();
+    assert_eq!(found.fee, bridge.fee);
+    let found = rpc.receipt(encode_txid(&receipt.anchor)).await?.unwrap();
+    assert_eq!(found.txid, encode_txid(&receipt.txid));
+    assert!(rpc.bridge("invalid txid".into()).await.is_err());
+
+    let proof = rpc
+        .inclusion_proof(
+            encode_txid(&receipt.txid),
+            Some(encode_txid(&receipt.anchor)),
+        )
+        .await?
+        .unwrap();
+    assert_eq!(proof.header.slot, 1);
+    assert_eq!(proof.count, 2);
+
+    let status = rpc.pool_status().await?;
+    assert_eq!(status.receipts, 0);
+
+    drop(rpc);
+    std::fs::remove_dir_all(path)?;
+    Ok(())
+}
@@ -4,7 +4,11 @@ use anyhow::Result;
 use parity_db::{BTreeIterator, ColumnOptions, Db, Operation as Op, Options};
 use runtime::storage::{Commit, Operation, Storage};
 use std::{path::PathBuf, sync::Arc};
-use zcore::Block;
+use zcore::{
+    codec,
+    ex::{Bridge, Receipt},
+    Block, Hash,
+};
 
 /// The state column
 pub const STATE_COLUMN: u8 = 0;
@@ -15,6 +19,15 @@ pub const BLOCK_COLUMN: u8 = 1;
 /// The transaction column
 pub const TRANSACTION_COLUMN: u8 = 2;
 
+/// The key prefix of the slot index in the block column
+const SLOT_PREFIX: &[u8] = b"slot";
+
+/// The key prefix of the receipts in the transaction column
+const RECEIPT_PREFIX: &[u8] = b"receipt";
+
+/// The key prefix of the transaction slot index in the transaction column
+const TX_SLOT_PREFIX: &[u8] = b"txslot";
+
 /// The parity database storage
 #[derive(Clone)]
 pub struct Parity(Arc<Db>);
@@ -41,21 +54,60 @@ impl Storage for Parity {
     }
 
     fn set_block(&self, block: &Block) -> Result<()> {
-        self.0.commit_changes(vec![(
-            BLOCK_COLUMN,
-            Op::Set(block.header.hash().to_vec(), postcard::to_allocvec(&block)?),
-        )])?;
+        let hash = block.header.hash();
+        let slot = [SLOT_PREFIX, &block.header.slot.to_be_bytes()].concat();
+        self.0.commit_changes(vec![
+            (BLOCK_COLUMN, Op::Set(hash.to_vec(), block.encode()?)),
+            (BLOCK_COLUMN, Op::Set(slot, hash.to_vec())),
+        ])?;
         Ok(())
     }
 
-    fn set_txs(&self, txs: Vec<Vec<u8>>) -> Result<()> {
-        self.0.commit_changes(
-            txs.into_iter()
-                .map(|tx| (TRANSACTION_COLUMN, Op::Set(tx.to_vec(), vec![0]))),
-        )?;
+    fn block(&self, hash: &Hash) -> Result<Option<Block>> {
+        self.0
+            .get(BLOCK_COLUMN, hash)?
+            .map(|value| Block::decode(&value))
+            .transpose()
+    }
+
+    fn block_hash(&self, slot: u32) -> Result<Option<Hash>> {
+        let key = [SLOT_PREFIX, &slot.to_be_bytes()].concat();
+        let Some(value) = self.0.get(BLOCK_COLUMN, &key)? else {
+            return Ok(None);
+        };
+
+        let hash = value
+            .try_into()
+            .map_err(|e| anyhow::anyhow!("Invalid block hash of slot {slot}: {e:?}"))?;
+        Ok(Some(hash))
+    }
+
+    fn set_txs(&self, slot: u32, txs: Vec<Vec<u8>>) -> Result<()> {
+        let mut ops = Vec::new();
+        for tx in txs {
+            let index = [TX_SLOT_PREFIX, &tx].concat();
+            ops.push((
+                TRANSACTION_COLUMN,
+                Op::Set(index, slot.to_be_bytes().to_vec()),
+            ));
+            ops.push((TRANSACTION_COLUMN, Op::Set(tx, vec![0])));
+        }
+        self.0.commit_changes(ops)?;
         Ok(())
     }
 
+    fn tx_slot(&self, txid: &[u8]) -> Result<Option<u32>> {
+        let key = [TX_SLOT_PREFIX, txid].concat();
+        let Some(value) = self.0.get(TRANSACTION_COLUMN, &key)? else {
+            return Ok(None);
+        };
+
+        let slot = value
+            .try_into()
+            .map_err(|e| anyhow::anyhow!("Invalid slot index of transaction: {e:?}"))?;
+        Ok(Some(u32::from_be_bytes(slot)))
+    }
+
     fn exists(&self, key: &[u8]) -> Result<bool> {
         self.0
             .get(TRANSACTION_COLUMN, key)
@@ -63,6 +115,48 @@ impl Storage for Parity {
             .map_err(Into::into)
     }
 
+    fn set_bridges(&self, bridges: Vec<Bridge>) -> Result<()> {
+        let mut ops = Vec::new();
+        for bridge in bridges {
+            ops.push((
+                TRANSACTION_COLUMN,
+                Op::Set(bridge.txid.clone(), codec::encode(&bridge)?),
+            ));
+        }
+        self.0.commit_changes(ops)?;
+        Ok(())
+    }
+
+    fn bridge(&self, txid: &[u8]) -> Result<Option<Bridge>> {
+        // NOTE: the receipts are stored with the placeholder value
+        match self.0.get(TRANSACTION_COLUMN, txid)? {
+            Some(value) if value != [0] => Ok(Some(codec::decode::<Bridge, Bridge>(&value)?)),
+            _ => Ok(None),
+        }
+    }
+
+    fn set_receipts(&self, receipts: Vec<Receipt>) -> Result<()> {
+        let mut ops = Vec::new();
+        for receipt in receipts {
+            ops.push((
+                TRANSACTION_COLUMN,
+                Op::Set(
+                    [RECEIPT_PREFIX, &receipt.anchor].concat(),
+                    codec::encode(&receipt)?,
+                ),
+            ));
+        }
+        self.0.commit_changes(ops)?;
+        Ok(())
+    }
+
+    fn receipt(&self, anchor: &[u8]) -> Result<Option<Receipt>> {
+        self.0
+            .get(TRANSACTION_COLUMN, &[RECEIPT_PREFIX, anchor].concat())?
+            .map(|value| codec::decode::<Receipt, Receipt>(&value))
+            .transpose()
+    }
+
     fn root(&self) -> Result<[u8; 32]> {
         let mut leaves = Vec::new();
         let iter = ParityIter(self.0.iter(STATE_COLUMN)?);
@@ -0,0 +1,298 @@
+//! Typed client for the Zosh JSON RPC API.
+
+#![cfg(feature = "client")]
+
+use crate::{
+    types::encode_txid, ApiClient, BlockId, BlockInterface, Encoding, PoolStatus, RpcBridge,
+    RpcHeader, RpcReceipt, SubmitAck, TransactionStatus, Validators,
+};
+use anyhow::Result;
+use jsonrpsee::ws_client::{HeaderMap, HeaderValue, WsClient, WsClientBuilder};
+use std::{ops::RangeInclusive, sync::Arc, time::Duration};
+use tokio::sync::{mpsc, Mutex};
+use zcore::{ex::Bridge, InclusionProof, State};
+
+/// The delay before reconnecting to the node
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+/// The RPC client reconnecting on disconnection
+#[derive(Clone)]
+pub struct Client {
+    /// The websocket URL of the node
+    url: String,
+
+    /// The headers of the handshake, e.g. the bearer token
+    headers: HeaderMap,
+
+    /// The current connection
+    inner: Arc<Mutex<Arc<WsClient>>>,
+}
+
+impl Client {
+    /// Connect to the node
+    pub async fn connect(url: impl Into<String>) -> Result<Self> {
+        Self::connect_with_token(url, None).await
+    }
+
+    /// Connect to the node with the bearer token of the protected methods
+    pub async fn connect_with_token(url: impl Into<String>, token: Option<&str>) -> Result<Self> {
+        let url = url.into();
+        let headers = headers(token)?;
+        let inner = WsClientBuilder::default()
+            .set_headers(headers.clone())
+            .build(&url)
+            .await?;
+        Ok(Self {
+            url,
+            headers,
+            inner: Arc::new(Mutex::new(Arc::new(inner))),
+        })
+    }
+
+    /// Get the connection, reconnecting if it is dropped
+    pub async fn rpc(&self) -> Result<Arc<WsClient>> {
+        let mut inner = self.inner.lock().await;
+        if !inner.is_connected() {
+            tracing::info!("Reconnecting to Zosh RPC at {}", self.url);
+            *inner = Arc::new(
+                WsClientBuilder::default()
+                    .set_headers(self.headers.clone())
+                    .build(&self.url)
+                    .await?,
+            );
+        }
+        Ok(inner.clone())
+    }
+
+    /// Get the chain state
+    pub async fn chain(&self) -> Result<State> {
+        Ok(self.rpc().await?.chain().await?)
+    }
+
+    /// Get the block by its slot or hash
+    pub async fn block(&self, id: BlockId, encoding: Encoding) -> Result<Option<BlockInterface>> {
+        let rpc = self.rpc().await?;
+        let block = match id {
+            BlockId::Slot(slot) => rpc.block_by_slot(slot, Some(encoding)).await?,
+            BlockId::Hash(hash) => rpc.block_by_hash(hash, Some(encoding)).await?,
+        };
+        Ok(block)
+    }
+
+    /// Get the block header by its slot or hash
+    pub async fn header(&self, id: BlockId) -> Result<Option<RpcHeader>> {
+        Ok(self.rpc().await?.header(id).await?)
+    }
+
+    /// Get the bridge request by its source txid
+    pub async fn bridge(&self, txid: &str) -> Result<Option<RpcBridge>> {
+        Ok(self.rpc().await?.bridge(txid.into()).await?)
+    }
+
+    /// Get the receipt by the source txid of its bridge request
+    pub async fn receipt(&self, anchor: &str) -> Result<Option<RpcReceipt>> {
+        Ok(self.rpc().await?.receipt(anchor.into()).await?)
+    }
+
+    /// Get the inclusion proof of the txid, decoded for verification
+    ///
+    /// The receipts are proven with the anchors of their bridge requests.
+    pub async fn inclusion_proof(
+        &self,
+        txid: &str,
+        anchor: Option<&str>,
+    ) -> Result<Option<InclusionProof>> {
+        self.rpc()
+            .await?
+            .inclusion_proof(txid.into(), anchor.map(Into::into))
+            .await?
+            .as_ref()
+            .map(InclusionProof::try_from)
+            .transpose()
+    }
+
+    /// Get the status of the mempool
+    pub async fn pool_status(&self) -> Result<PoolStatus> {
+        Ok(self.rpc().await?.pool_status().await?)
+    }
+
+    /// Get the current validator set
+    pub async fn validators(&self) -> Result<Validators> {
+        Ok(self.rpc().await?.validators().await?)
+    }
+
+    /// Submit a bridge request
+    ///
+    /// The fee must be the one charged by the fee schedule of the chain
+    /// state, the mismatching fees are rejected.
+    pub async fn submit_bridge(&self, bridge: Bridge) -> Result<SubmitAck> {
+        let bridge = RpcBridge::from(&bridge);
+        Ok(self.rpc().await?.submit_bridge(bridge).await?)
+    }
+
+    /// Watch the new blocks
+    ///
+    /// Resubscribes on disconnection, the blocks produced in between are
+    /// backfilled so the receiver sees every slot in order.
+    pub fn watch_blocks(&self, encoding: Encoding) -> mpsc::Receiver<BlockInterface> {
+        let (tx, rx) = mpsc::channel(64);
+        let client = self.clone();
+        tokio::spawn(async move {
+            let mut last = None;
+            while !tx.is_closed() {
+                if let Err(e) = client.forward_blocks(encoding, &mut last, &tx).await {
+                    tracing::warn!("Block subscription dropped: {e:?}");
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+        rx
+    }
+
+    /// Watch the status of a transaction until it is terminal
+    ///
+    /// Resubscribes on disconnection, the node replays the current status
+    /// on subscribing, duplicates are skipped.
+    pub fn watch_transaction(&self, txid: Vec<u8>) -> mpsc::Receiver<TransactionStatus> {
+        let (tx, rx) = mpsc::channel(16);
+        let client = self.clone();
+        tokio::spawn(async move {
+            let mut last = None;
+            while !tx.is_closed() {
+                match client.forward_status(&txid, &mut last, &tx).await {
+                    Ok(()) => return,
+                    Err(e) => tracing::warn!("Transaction subscription dropped: {e:?}"),
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        });
+        rx
+    }
+
+    /// Subscribe to the blocks and forward them from the last seen slot
+    async fn forward_blocks(
+        &self,
+        encoding: Encoding,
+        last: &mut Option<u32>,
+        tx: &mpsc::Sender<BlockInterface>,
+    ) -> Result<()> {
+        let rpc = self.rpc().await?;
+        let mut subscription = rpc.subscribe_block(Some(encoding)).await?;
+        if last.is_some() {
+            let present = rpc.chain().await?.present.slot;
+            for slot in missed(*last, present).into_iter().flatten() {
+                if let Some(block) = rpc.block_by_slot(slot, Some(encoding)).await? {
+                    forward(block, last, tx).await?;
+                }
+            }
+        }
+
+        while let Some(block) = subscription.next().await {
+            forward(block?, last, tx).await?;
+        }
+        anyhow::bail!("Block subscription closed")
+    }
+
+    /// Subscribe to the transaction and forward the new status
+    async fn forward_status(
+        &self,
+        txid: &[u8],
+        last: &mut Option<TransactionStatus>,
+        tx: &mpsc::Sender<TransactionStatus>,
+    ) -> Result<()> {
+        let rpc = self.rpc().await?;
+        let mut subscription = rpc.subscribe_transaction(encode_txid(txid)).await?;
+        while let Some(status) = subscription.next().await {
+            let status = status?;
+            if last.as_ref() == Some(&status) {
+                continue;
+            }
+
+            let terminal = status.is_terminal();
+            *last = Some(status.clone());
+            tx.send(status).await?;
+            if terminal {
+                return Ok(());
+            }
+        }
+        anyhow::bail!("Transaction subscription closed")
+    }
+}
+
+/// The headers of the handshake with the bearer token
+fn headers(token: Option<&str>) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    if let Some(token) = token {
+        headers.insert(
+            "Authorization",
+            HeaderValue::from_str(&format!("Bearer {token}"))?,
+        );
+    }
+    Ok(headers)
+}
+
+/// The slots missed since the last seen slot
+fn missed(last: Option<u32>, present: u32) -> Option<RangeInclusive<u32>> {
+    last.filter(|last| *last < present)
+        .map(|last| last + 1..=present)
+}
+
+/// Forward the block if it is newer than the last seen slot
+async fn forward(
+    block: BlockInterface,
+    last: &mut Option<u32>,
+    tx: &mpsc::Sender<BlockInterface>,
+) -> Result<()> {
+    let slot = block.slot()?;
+    if last.is_some_and(|last| slot <= last) {
+        return Ok(());
+    }
+
+    *last = Some(slot);
+    tx.send(block).await?;
+    Ok(())
+}
+
+#[test]
+fn test_headers() -> Result<()> {
+    assert!(headers(None)?.is_empty());
+    assert_eq!(headers(Some("secret"))?["Authorization"], "Bearer secret");
+    assert!(headers(Some("invalid\ntoken")).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_missed() {
+    assert_eq!(missed(None, 10), None);
+    assert_eq!(missed(Some(7), 10), Some(8..=10));
+    assert_eq!(missed(Some(10), 10), None);
+    assert_eq!(missed(Some(u32::MAX), u32::MAX), None);
+}
+
+#[tokio::test]
+async fn test_forward() -> Result<()> {
+    use zcore::Block;
+
+    let (tx, mut rx) = mpsc::channel(8);
+    let mut last = None;
+    for slot in [3, 4, 4, 2, 6] {
+        let mut block = Block::default();
+        block.header.slot = slot;
+        forward(
+            BlockInterface::new(&block, Encoding::Base64)?,
+            &mut last,
+            &tx,
+        )
+        .await?;
+    }
+    drop(tx);
+
+    // the duplicated and the older blocks of the resubscriptions are skipped
+    let mut slots = Vec::new();
+    while let Some(block) = rx.recv().await {
+        slots.push(block.slot()?);
+    }
+    assert_eq!(slots, [3, 4, 6]);
+    assert_eq!(last, Some(6));
+    Ok(())
+}
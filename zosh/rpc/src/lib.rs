@@ -1,10 +1,19 @@
 //! Zosh JSON RPC API.
 
+use base64::{engine::general_purpose::STANDARD, Engine};
+#[cfg(feature = "client")]
+pub use client::Client;
 use jsonrpsee::{core::SubscriptionResult, proc_macros::rpc, types::ErrorObjectOwned};
 use serde::{Deserialize, Serialize};
+pub use types::{
+    BlockId, BridgeEvent, BridgeFilter, Encoding, PoolStatus, RpcBlock, RpcBridge, RpcBundle,
+    RpcHeader, RpcInclusionProof, RpcReceipt, SubmitAck, Validators,
+};
 use zcore::{Block, State};
 
+mod client;
 pub mod server;
+pub mod types;
 
 #[cfg_attr(
     all(feature = "client", feature = "server"),
@@ -17,24 +26,184 @@ pub trait Api {
     #[method(name = "chainInfo")]
     async fn chain(&self) -> Result<State, ErrorObjectOwned>;
 
+    /// Get the block by its base58 hash.
+    #[method(name = "getBlockByHash")]
+    async fn block_by_hash(
+        &self,
+        hash: String,
+        encoding: Option<Encoding>,
+    ) -> Result<Option<BlockInterface>, ErrorObjectOwned>;
+
+    /// Get the block by its slot.
+    #[method(name = "getBlockBySlot")]
+    async fn block_by_slot(
+        &self,
+        slot: u32,
+        encoding: Option<Encoding>,
+    ) -> Result<Option<BlockInterface>, ErrorObjectOwned>;
+
+    /// Get the block header by its slot or base58 hash.
+    #[method(name = "getHeader")]
+    async fn header(&self, id: BlockId) -> Result<Option<RpcHeader>, ErrorObjectOwned>;
+
+    /// Get the bridge request by its source txid.
+    #[method(name = "getBridge")]
+    async fn bridge(&self, txid: String) -> Result<Option<RpcBridge>, ErrorObjectOwned>;
+
+    /// Get the receipt by the source txid of its bridge request.
+    #[method(name = "getReceipt")]
+    async fn receipt(&self, anchor: String) -> Result<Option<RpcReceipt>, ErrorObjectOwned>;
+
+    /// Get the inclusion proof of a txid in its finalized block, the
+    /// receipts are proven with their anchors.
+    #[method(name = "getInclusionProof")]
+    async fn inclusion_proof(
+        &self,
+        txid: String,
+        anchor: Option<String>,
+    ) -> Result<Option<RpcInclusionProof>, ErrorObjectOwned>;
+
+    /// Get the status of the mempool.
+    #[method(name = "getPoolStatus")]
+    async fn pool_status(&self) -> Result<PoolStatus, ErrorObjectOwned>;
+
+    /// Get the current validator set.
+    #[method(name = "getValidators")]
+    async fn validators(&self) -> Result<Validators, ErrorObjectOwned>;
+
+    /// Submit a bridge request from an external collector.
+    #[method(name = "submitBridge")]
+    async fn submit_bridge(&self, bridge: RpcBridge) -> Result<SubmitAck, ErrorObjectOwned>;
+
     /// Subscribe to new blocks.
     #[subscription(name = "subscribeBlock", item = BlockInterface)]
-    async fn subscribe_block(&self) -> SubscriptionResult;
+    async fn subscribe_block(&self, encoding: Option<Encoding>) -> SubscriptionResult;
 
-    /// Subscribe to transactions status.
-    #[subscription(name = "subscribeTransaction", item = Vec<u8>)]
-    async fn subscribe_transaction(&self, txid: Vec<u8>) -> SubscriptionResult;
+    /// Subscribe to the finalized bridge requests matching the filter.
+    #[subscription(name = "subscribeBridges", item = BridgeEvent)]
+    async fn subscribe_bridges(&self, filter: BridgeFilter) -> SubscriptionResult;
+
+    /// Subscribe to transactions status by the hex or base58 txid.
+    #[subscription(name = "subscribeTransaction", item = TransactionStatus)]
+    async fn subscribe_transaction(&self, txid: String) -> SubscriptionResult;
 }
 
-/// Block interface for the UI
-#[derive(Debug, Serialize, Deserialize)]
-pub struct BlockInterface {
-    pub block: Vec<u8>,
+/// The block in the requested encoding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "encoding", content = "block", rename_all = "lowercase")]
+pub enum BlockInterface {
+    /// The human-readable block
+    Json(RpcBlock),
+
+    /// The base64 encoded block in the canonical encoding
+    Base64(String),
+
+    /// The base58 encoded block in the canonical encoding
+    Base58(String),
 }
 
 impl BlockInterface {
+    /// Encode the block
+    pub fn new(block: &Block, encoding: Encoding) -> anyhow::Result<Self> {
+        Ok(match encoding {
+            Encoding::Json => Self::Json(RpcBlock::from(block)),
+            Encoding::Base64 => Self::Base64(STANDARD.encode(block.encode()?)),
+            Encoding::Base58 => Self::Base58(bs58::encode(block.encode()?).into_string()),
+        })
+    }
+
     /// Convert the block interface into a block
+    ///
+    /// Only the base64 and base58 blocks can be decoded.
     pub fn into_block(&self) -> anyhow::Result<Block> {
-        Ok(postcard::from_bytes(&self.block)?)
+        match self {
+            Self::Json(_) => anyhow::bail!("Json blocks can not be decoded, use base64 or base58"),
+            Self::Base64(data) => Block::decode(&STANDARD.decode(data)?),
+            Self::Base58(data) => Block::decode(&bs58::decode(data).into_vec()?),
+        }
+    }
+
+    /// Get the slot of the block
+    pub fn slot(&self) -> anyhow::Result<u32> {
+        match self {
+            Self::Json(block) => Ok(block.header.slot),
+            _ => Ok(self.into_block()?.header.slot),
+        }
     }
 }
+
+/// The status of a bridge transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum TransactionStatus {
+    /// The bridge request is validated and queued for bundling
+    Queued,
+
+    /// The bridge request is bundled and pushed into the pool
+    Bundled {
+        /// The hash of the bundle
+        bundle: Vec<u8>,
+    },
+
+    /// The bundle is included in a finalized block
+    Included {
+        /// The slot of the block
+        slot: u32,
+    },
+
+    /// The receipt of the bridge is included in a finalized block
+    Confirmed {
+        /// The slot of the block
+        slot: u32,
+
+        /// The txid of the transaction on the target chain
+        receipt: Vec<u8>,
+    },
+
+    /// The bridge request is rejected
+    Failed {
+        /// The reason of the failure
+        reason: String,
+    },
+}
+
+impl TransactionStatus {
+    /// If the status is terminal
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Confirmed { .. } | Self::Failed { .. })
+    }
+}
+
+#[test]
+fn test_block_interface() -> anyhow::Result<()> {
+    let mut block = Block::default();
+    block.header.version = zcore::HEADER_VERSION;
+    block.header.slot = 42;
+    block.header.votes.insert([1; 32], vec![2; 64]);
+
+    for encoding in [Encoding::Base64, Encoding::Base58] {
+        let interface = BlockInterface::new(&block, encoding)?;
+        let decoded = interface.into_block()?;
+        assert_eq!(decoded.header.hash(), block.header.hash());
+        assert_eq!(decoded.header.votes, block.header.votes);
+        assert_eq!(interface.slot()?, 42);
+
+        // the payload is tagged with its encoding
+        let json = serde_json::to_value(&interface)?;
+        assert_eq!(json["encoding"], serde_json::to_value(encoding)?);
+    }
+
+    let json = BlockInterface::new(&block, Encoding::default())?;
+    assert_eq!(json.slot()?, 42);
+    assert!(json.into_block().is_err());
+
+    let raw = serde_json::to_string(&json)?;
+    let BlockInterface::Json(decoded) = serde_json::from_str(&raw)? else {
+        anyhow::bail!("Expected json block");
+    };
+    assert_eq!(
+        decoded.header.hash,
+        bs58::encode(block.header.hash()).into_string()
+    );
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Access control middleware
+
+use super::{Limiter, Policy};
+use jsonrpsee::{
+    core::middleware::{Batch, BatchEntry, Notification},
+    server::{middleware::rpc::RpcServiceT, MethodResponse},
+    types::{ErrorCode, ErrorObjectOwned, Id, Request},
+};
+use std::{future::Future, net::IpAddr, sync::Arc};
+
+/// The error code of the unauthorized requests
+pub const UNAUTHORIZED_CODE: i32 = -32001;
+
+/// The error code of the rate limited requests
+pub const RATE_LIMITED_CODE: i32 = -32005;
+
+/// Access control of a connection
+///
+/// Enforces the bearer token and the rate limits, the subscription cap is
+/// enforced by the server on the live sinks of the connection.
+#[derive(Clone)]
+pub struct Guard<S> {
+    /// The inner service
+    pub service: S,
+
+    /// The remote IP of the connection
+    pub ip: IpAddr,
+
+    /// If the connection carries the bearer token
+    pub authorized: bool,
+
+    /// The access policy
+    pub policy: Arc<Policy>,
+
+    /// The shared rate limiter
+    pub limiter: Arc<Limiter>,
+}
+
+impl<S> Guard<S> {
+    /// Check if the method is allowed
+    fn check(&self, method: &str) -> Result<(), ErrorObjectOwned> {
+        if !self.authorized && self.policy.is_protected(method) {
+            return Err(error(UNAUTHORIZED_CODE, "Unauthorized"));
+        }
+
+        if !self.limiter.check(&self.policy, self.ip, method) {
+            return Err(error(RATE_LIMITED_CODE, "Rate limited"));
+        }
+
+        Ok(())
+    }
+}
+
+impl<S> RpcServiceT for Guard<S>
+where
+    S: RpcServiceT<
+            MethodResponse = MethodResponse,
+            BatchResponse = MethodResponse,
+            NotificationResponse = MethodResponse,
+        > + Send
+        + Sync
+        + Clone
+        + 'static,
+{
+    type MethodResponse = S::MethodResponse;
+    type NotificationResponse = S::NotificationResponse;
+    type BatchResponse = S::BatchResponse;
+
+    fn batch<'a>(&self, batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+        let service = self.service.clone();
+        let checked = batch.iter().flatten().try_for_each(|entry| match entry {
+            BatchEntry::Call(request) if is_subscribe(request.method_name()) => Err(error(
+                ErrorCode::InvalidRequest.code(),
+                "Subscriptions are not allowed in batches",
+            )),
+            entry => self.check(entry.method_name()),
+        });
+
+        async move {
+            match checked {
+                Ok(()) => service.batch(batch).await,
+                Err(e) => MethodResponse::error(Id::Null, e),
+            }
+        }
+    }
+
+    fn call<'a>(
+        &self,
+        request: Request<'a>,
+    ) -> impl Future<Output = Self::MethodResponse> + Send + 'a {
+        let service = self.service.clone();
+        let checked = self.check(request.method_name());
+        async move {
+            match checked {
+                Ok(()) => service.call(request).await,
+                Err(e) => MethodResponse::error(request.id(), e),
+            }
+        }
+    }
+
+    fn notification<'a>(
+        &self,
+        n: Notification<'a>,
+    ) -> impl Future<Output = Self::NotificationResponse> + Send + 'a {
+        self.service.notification(n)
+    }
+}
+
+/// If the method opens a subscription
+fn is_subscribe(method: &str) -> bool {
+    method.starts_with("zosh_subscribe")
+}
+
+/// Create the error object
+fn error(code: i32, message: &str) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(code, message, Option::<()>::None)
+}
@@ -0,0 +1,178 @@
+//! Request metrics middleware
+
+use crate::server::prometheus::Exposition;
+use jsonrpsee::{
+    core::middleware::{Batch, Notification},
+    server::{middleware::rpc::RpcServiceT, MethodResponse},
+    types::Request,
+};
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use zcore::metrics::Histogram;
+
+/// The upper bounds of the latency histogram buckets in seconds
+pub const LATENCY_BUCKETS: [f64; 10] =
+    [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+/// The request metrics of a method
+#[derive(Debug, Clone)]
+pub struct MethodMetrics {
+    /// The latency of the requests
+    pub latency: Histogram,
+
+    /// The number of failed requests by error code
+    pub errors: BTreeMap<i32, u64>,
+}
+
+impl Default for MethodMetrics {
+    fn default() -> Self {
+        Self {
+            latency: Histogram::new(&LATENCY_BUCKETS),
+            errors: BTreeMap::new(),
+        }
+    }
+}
+
+impl MethodMetrics {
+    /// Record a request
+    fn record(&mut self, elapsed: Duration, error: Option<i32>) {
+        self.latency.observe(elapsed);
+        if let Some(code) = error {
+            *self.errors.entry(code).or_default() += 1;
+        }
+    }
+}
+
+/// The request metrics of the RPC server
+#[derive(Debug, Clone, Default)]
+pub struct Metrics(Arc<Mutex<BTreeMap<String, MethodMetrics>>>);
+
+impl Metrics {
+    /// Record a request of the method
+    pub fn record(&self, method: &str, elapsed: Duration, error: Option<i32>) {
+        let mut metrics = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        match metrics.get_mut(method) {
+            Some(metrics) => metrics.record(elapsed, error),
+            None => {
+                let mut method_metrics = MethodMetrics::default();
+                method_metrics.record(elapsed, error);
+                metrics.insert(method.to_string(), method_metrics);
+            }
+        }
+    }
+
+    /// Snapshot the metrics of all methods
+    pub fn snapshot(&self) -> BTreeMap<String, MethodMetrics> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Export the metrics of all methods
+    pub fn export(&self, exposition: &mut Exposition) {
+        let snapshot = self.snapshot();
+        exposition.family(
+            "zosh_rpc_request_seconds",
+            "histogram",
+            "The latency of the RPC requests",
+        );
+        for (method, metrics) in &snapshot {
+            exposition.histogram(
+                "zosh_rpc_request_seconds",
+                &[("method", method)],
+                &metrics.latency,
+            );
+        }
+
+        exposition.family(
+            "zosh_rpc_errors_total",
+            "counter",
+            "The failed RPC requests by error code",
+        );
+        for (method, metrics) in &snapshot {
+            for (code, count) in &metrics.errors {
+                let code = code.to_string();
+                exposition.sample(
+                    "zosh_rpc_errors_total",
+                    &[("method", method), ("code", &code)],
+                    *count as f64,
+                );
+            }
+        }
+    }
+}
+
+/// Metrics service recording the latency and the errors
+#[derive(Clone)]
+pub struct Metered<S> {
+    /// The inner service
+    pub service: S,
+
+    /// The shared metrics
+    pub metrics: Metrics,
+}
+
+impl<S> RpcServiceT for Metered<S>
+where
+    S: RpcServiceT<
+            MethodResponse = MethodResponse,
+            BatchResponse = MethodResponse,
+            NotificationResponse = MethodResponse,
+        > + Send
+        + Sync
+        + Clone
+        + 'static,
+{
+    type MethodResponse = S::MethodResponse;
+    type NotificationResponse = S::NotificationResponse;
+    type BatchResponse = S::BatchResponse;
+
+    fn batch<'a>(&self, batch: Batch<'a>) -> impl Future<Output = Self::BatchResponse> + Send + 'a {
+        let service = self.service.clone();
+        let metrics = self.metrics.clone();
+        async move {
+            let now = Instant::now();
+            let response = service.batch(batch).await;
+            metrics.record("batch", now.elapsed(), response.as_error_code());
+            response
+        }
+    }
+
+    fn call<'a>(
+        &self,
+        request: Request<'a>,
+    ) -> impl Future<Output = Self::MethodResponse> + Send + 'a {
+        let service = self.service.clone();
+        let metrics = self.metrics.clone();
+        async move {
+            let now = Instant::now();
+            let method = request.method_name().to_string();
+            let response = service.call(request).await;
+            metrics.record(&method, now.elapsed(), response.as_error_code());
+            response
+        }
+    }
+
+    fn notification<'a>(
+        &self,
+        n: Notification<'a>,
+    ) -> impl Future<Output = Self::NotificationResponse> + Send + 'a {
+        self.service.notification(n)
+    }
+}
+
+#[test]
+fn test_metrics_record() {
+    let metrics = Metrics::default();
+    metrics.record("zosh_chainInfo", Duration::from_micros(500), None);
+    metrics.record("zosh_chainInfo", Duration::from_secs(2), Some(-32005));
+
+    let snapshot = metrics.snapshot();
+    let chain = &snapshot["zosh_chainInfo"];
+    assert_eq!(chain.latency.count, 2);
+    assert_eq!(chain.latency.buckets[0], 1);
+    assert_eq!(chain.latency.buckets[LATENCY_BUCKETS.len()], 1);
+    assert_eq!(chain.errors[&-32005], 1);
+}
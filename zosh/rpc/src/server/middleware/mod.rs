@@ -1,5 +1,13 @@
 //! RPC middlewares
 
-pub use logger::Logger;
+pub use {
+    guard::Guard,
+    logger::Logger,
+    metrics::{Metered, MethodMetrics, Metrics, LATENCY_BUCKETS},
+    policy::{Limiter, Policy},
+};
 
+mod guard;
 mod logger;
+mod metrics;
+mod policy;
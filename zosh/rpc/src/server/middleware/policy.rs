@@ -0,0 +1,225 @@
+//! Access policy and rate limiter
+
+use http::{header::AUTHORIZATION, HeaderMap};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    hash::Hash,
+    net::IpAddr,
+    sync::Mutex,
+    time::Instant,
+};
+
+/// The maximum number of tracked rate limit buckets
+const MAX_BUCKETS: usize = 65_536;
+
+/// The access policy of the RPC server
+#[derive(Debug, Clone)]
+pub struct Policy {
+    /// The bearer token required by the protected methods
+    ///
+    /// The protected methods are open if no token is configured, the
+    /// server warns about it on startup.
+    pub token: Option<String>,
+
+    /// The methods requiring the bearer token
+    pub protected: Vec<String>,
+
+    /// The requests per second of an IP, 0 for unlimited
+    pub ip_rate: u32,
+
+    /// The requests per second of an IP on specific methods
+    pub method_rates: BTreeMap<String, u32>,
+
+    /// The maximum active subscriptions of a connection
+    pub max_subscriptions: usize,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            token: None,
+            protected: vec!["zosh_submitBridge".into()],
+            ip_rate: 100,
+            method_rates: [("zosh_submitBridge".into(), 5)].into(),
+            max_subscriptions: 64,
+        }
+    }
+}
+
+impl Policy {
+    /// Check if the request headers carry the bearer token
+    pub fn authorize(&self, headers: &HeaderMap) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+
+        headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|bearer| constant_eq(bearer.as_bytes(), token.as_bytes()))
+    }
+
+    /// Check if the method requires the bearer token
+    pub fn is_protected(&self, method: &str) -> bool {
+        self.protected.iter().any(|protected| protected == method)
+    }
+
+    /// Check if the protected methods are open to anyone
+    pub fn is_open(&self) -> bool {
+        self.token.is_none() && !self.protected.is_empty()
+    }
+}
+
+/// The token bucket rate limiter
+#[derive(Debug, Default)]
+pub struct Limiter {
+    /// The buckets of the IPs
+    ips: Mutex<Buckets<IpAddr>>,
+
+    /// The buckets of the IPs on the specific methods
+    methods: Mutex<Buckets<(IpAddr, String)>>,
+}
+
+impl Limiter {
+    /// Take a token for the request, returns false if rate limited
+    pub fn check(&self, policy: &Policy, ip: IpAddr, method: &str) -> bool {
+        if !take(&self.ips, ip, policy.ip_rate) {
+            return false;
+        }
+
+        match policy.method_rates.get(method) {
+            Some(rate) => take(&self.methods, (ip, method.to_string()), *rate),
+            None => true,
+        }
+    }
+}
+
+/// Take a token from the bucket of the key
+fn take<K: Eq + Hash + Clone>(buckets: &Mutex<Buckets<K>>, key: K, rate: u32) -> bool {
+    if rate == 0 {
+        return true;
+    }
+
+    buckets
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take(key, rate)
+}
+
+/// The rate limit buckets
+///
+/// The oldest buckets are evicted first once the buckets are full, so the
+/// requests from many addresses never grow them without bound.
+#[derive(Debug)]
+struct Buckets<K> {
+    /// The buckets of the keys
+    buckets: HashMap<K, Bucket>,
+
+    /// The keys in the order of being tracked
+    order: VecDeque<K>,
+}
+
+impl<K> Default for Buckets<K> {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> Buckets<K> {
+    /// Take a token from the bucket of the key, evicting the oldest ones
+    fn take(&mut self, key: K, rate: u32) -> bool {
+        if let Some(bucket) = self.buckets.get_mut(&key) {
+            return bucket.take(rate);
+        }
+
+        while self.buckets.len() >= MAX_BUCKETS {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            self.buckets.remove(&oldest);
+        }
+
+        let mut bucket = Bucket::new(rate);
+        let taken = bucket.take(rate);
+        self.order.push_back(key.clone());
+        self.buckets.insert(key, bucket);
+        taken
+    }
+}
+
+/// The token bucket refilled at the rate per second
+#[derive(Debug)]
+struct Bucket {
+    /// The available tokens
+    tokens: f64,
+
+    /// The last refill time
+    updated: Instant,
+}
+
+impl Bucket {
+    /// Create a full bucket
+    fn new(rate: u32) -> Self {
+        Self {
+            tokens: rate as f64,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Refill the bucket and take a token
+    fn take(&mut self, rate: u32) -> bool {
+        let now = Instant::now();
+        let rate = rate as f64;
+        let refill = now.duration_since(self.updated).as_secs_f64() * rate;
+        self.tokens = (self.tokens + refill).min(rate);
+        self.updated = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// Compare the bytes in constant time
+fn constant_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[test]
+fn test_limiter() {
+    let policy = Policy {
+        ip_rate: 3,
+        method_rates: [("zosh_submitBridge".into(), 1)].into(),
+        ..Default::default()
+    };
+
+    let limiter = Limiter::default();
+    let ip = IpAddr::from([127, 0, 0, 1]);
+    assert!(limiter.check(&policy, ip, "zosh_submitBridge"));
+    assert!(!limiter.check(&policy, ip, "zosh_submitBridge"));
+    assert!(limiter.check(&policy, ip, "zosh_chainInfo"));
+    assert!(!limiter.check(&policy, ip, "zosh_chainInfo"));
+    assert!(limiter.check(&policy, IpAddr::from([127, 0, 0, 2]), "zosh_chainInfo"));
+}
+
+#[test]
+fn test_bucket_eviction() {
+    let mut buckets = Buckets::default();
+    for key in 0..MAX_BUCKETS {
+        assert!(buckets.take(key, 1));
+    }
+    assert!(!buckets.take(0, 1));
+
+    // the oldest bucket is evicted by the new key
+    assert!(buckets.take(MAX_BUCKETS, 1));
+    assert_eq!(buckets.buckets.len(), MAX_BUCKETS);
+    assert_eq!(buckets.order.len(), MAX_BUCKETS);
+    assert!(buckets.take(0, 1));
+    assert!(!buckets.take(2, 1));
+}
@@ -2,13 +2,165 @@
 
 #![cfg(feature = "server")]
 
+use anyhow::Result;
+use http::{header::CONTENT_TYPE, StatusCode};
 pub use jsonrpsee::{
     core::{middleware::RpcServiceBuilder, SubscriptionResult},
     server::Server,
     types::{ErrorCode, ErrorObjectOwned},
     PendingSubscriptionSink,
 };
-pub use sub::SubscriptionManager;
+use jsonrpsee::{
+    server::{
+        serve_with_graceful_shutdown, stop_channel, HttpBody, HttpRequest, HttpResponse,
+        ServerConfig,
+    },
+    Methods,
+};
+use middleware::{Guard, Limiter, Logger, Metered, Metrics, Policy};
+pub use prometheus::{Exporter, Exposition, METRICS_PATH};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+pub use sub::{StatusCache, SubscriptionManager};
+use tokio::net::TcpListener;
+use tower::Service;
 
 pub mod middleware;
+mod prometheus;
 mod sub;
+
+/// Serve the RPC methods with the access policy
+///
+/// The middlewares are built per connection, the rate limits are tracked
+/// by the remote IP and the live subscription sinks by the connection, a
+/// sink releases its slot once it is dropped. The metrics of the exporter
+/// are served on [`METRICS_PATH`] behind the bearer token and the rate
+/// limits of the policy.
+pub async fn serve(
+    addr: SocketAddr,
+    methods: impl Into<Methods>,
+    policy: Policy,
+    metrics: Metrics,
+    exporter: impl Exporter,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!("Listening RPC on {}", listener.local_addr()?);
+    if policy.is_open() {
+        tracing::warn!(
+            "No RPC token configured, the protected methods {:?} and the metrics are open to anyone",
+            policy.protected
+        );
+    }
+
+    let methods: Methods = methods.into();
+    let policy = Arc::new(policy);
+    let limiter = Arc::new(Limiter::default());
+    let config = ServerConfig::builder()
+        .max_subscriptions_per_connection(policy.max_subscriptions as u32)
+        .build();
+    let builder = Server::builder().set_config(config).to_service_builder();
+
+    // NOTE: the connections are shut down once the server handle is dropped
+    let (stop_handle, _server_handle) = stop_channel();
+    loop {
+        let (stream, remote) = listener.accept().await?;
+        let (methods, stop, builder) = (methods.clone(), stop_handle.clone(), builder.clone());
+        let (policy, limiter, metrics) = (policy.clone(), limiter.clone(), metrics.clone());
+        let exporter = exporter.clone();
+        let service = tower::service_fn(move |request: HttpRequest<hyper::body::Incoming>| {
+            let authorized = policy.authorize(request.headers());
+            let metered = (request.uri().path() == METRICS_PATH)
+                .then(|| scrape(&policy, &limiter, remote.ip(), authorized));
+            let (policy, limiter) = (policy.clone(), limiter.clone());
+            let metrics = metrics.clone();
+            let rpc_middleware = RpcServiceBuilder::new()
+                .layer_fn(move |service| Metered {
+                    service,
+                    metrics: metrics.clone(),
+                })
+                .layer_fn(move |service| Guard {
+                    service,
+                    ip: remote.ip(),
+                    authorized,
+                    policy: policy.clone(),
+                    limiter: limiter.clone(),
+                })
+                .layer_fn(Logger);
+
+            let mut service = builder
+                .clone()
+                .set_rpc_middleware(rpc_middleware)
+                .build(methods.clone(), stop.clone());
+            let exporter = exporter.clone();
+            async move {
+                match metered {
+                    Some(StatusCode::OK) => Ok(export(&exporter).await),
+                    Some(status) => Ok(reject(status)),
+                    None => service.call(request).await,
+                }
+            }
+        });
+
+        tokio::spawn(serve_with_graceful_shutdown(
+            stream,
+            service,
+            stop_handle.clone().shutdown(),
+        ));
+    }
+}
+
+/// Check the scrape of the metrics against the policy
+fn scrape(policy: &Policy, limiter: &Limiter, ip: IpAddr, authorized: bool) -> StatusCode {
+    if !authorized {
+        StatusCode::UNAUTHORIZED
+    } else if !limiter.check(policy, ip, METRICS_PATH) {
+        StatusCode::TOO_MANY_REQUESTS
+    } else {
+        StatusCode::OK
+    }
+}
+
+/// Reject the request with the status
+fn reject(status: StatusCode) -> HttpResponse {
+    let mut response = HttpResponse::new(HttpBody::from(status.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+/// Render the metrics of the exporter into the response
+async fn export(exporter: &impl Exporter) -> HttpResponse {
+    match exporter.export().await {
+        Ok(text) => http::Response::builder()
+            .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+            .body(HttpBody::from(text)),
+        Err(e) => {
+            tracing::error!("Failed to export the metrics: {e:?}");
+            http::Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(HttpBody::from(e.to_string()))
+        }
+    }
+    .unwrap_or_else(|e| HttpResponse::new(HttpBody::from(e.to_string())))
+}
+
+#[test]
+fn test_scrape() {
+    let policy = Policy {
+        ip_rate: 1,
+        ..Default::default()
+    };
+    let limiter = Limiter::default();
+    let ip = IpAddr::from([127, 0, 0, 1]);
+
+    assert_eq!(
+        scrape(&policy, &limiter, ip, false),
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(scrape(&policy, &limiter, ip, true), StatusCode::OK);
+    assert_eq!(
+        scrape(&policy, &limiter, ip, true),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
@@ -0,0 +1,122 @@
+//! Prometheus text exposition of the node metrics
+
+use anyhow::Result;
+use std::{fmt::Write, future::Future};
+use zcore::metrics::Histogram;
+
+/// The path of the metrics endpoint
+pub const METRICS_PATH: &str = "/metrics";
+
+/// The exporter of the metrics served on [`METRICS_PATH`]
+pub trait Exporter: Clone + Send + Sync + 'static {
+    /// Render the metrics in the prometheus text format
+    fn export(&self) -> impl Future<Output = Result<String>> + Send;
+}
+
+/// Builder of the prometheus text format
+#[derive(Debug, Default)]
+pub struct Exposition(String);
+
+impl Exposition {
+    /// Declare a metric family
+    pub fn family(&mut self, name: &str, kind: &str, help: &str) -> &mut Self {
+        let _ = writeln!(self.0, "# HELP {name} {help}");
+        let _ = writeln!(self.0, "# TYPE {name} {kind}");
+        self
+    }
+
+    /// Write a sample of the metric
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        self.0.push_str(name);
+        if !labels.is_empty() {
+            let labels = labels
+                .iter()
+                .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+                .collect::<Vec<_>>()
+                .join(",");
+            let _ = write!(self.0, "{{{labels}}}");
+        }
+        let _ = writeln!(self.0, " {value}");
+        self
+    }
+
+    /// Declare a gauge with a single sample
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) -> &mut Self {
+        self.family(name, "gauge", help).sample(name, &[], value)
+    }
+
+    /// Write the samples of a histogram
+    pub fn histogram(
+        &mut self,
+        name: &str,
+        labels: &[(&str, &str)],
+        histogram: &Histogram,
+    ) -> &mut Self {
+        let Histogram {
+            bounds,
+            buckets,
+            sum,
+            count,
+        } = histogram;
+        let bucket = format!("{name}_bucket");
+        let mut cumulative = 0;
+        for (bound, value) in bounds.iter().zip(buckets) {
+            cumulative += value;
+            let le = bound.to_string();
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &le));
+            self.sample(&bucket, &bucket_labels, cumulative as f64);
+        }
+
+        let mut bucket_labels = labels.to_vec();
+        bucket_labels.push(("le", "+Inf"));
+        self.sample(&bucket, &bucket_labels, *count as f64)
+            .sample(&format!("{name}_sum"), labels, *sum)
+            .sample(&format!("{name}_count"), labels, *count as f64)
+    }
+
+    /// Finish the exposition
+    pub fn finish(self) -> String {
+        self.0
+    }
+}
+
+/// Escape the label value
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[test]
+fn test_exposition() {
+    let mut exposition = Exposition::default();
+    exposition
+        .gauge("zosh_slot", "The slot of the latest block", 42.0)
+        .family("zosh_latency_seconds", "histogram", "The latency")
+        .histogram(
+            "zosh_latency_seconds",
+            &[("chain", "Solana")],
+            &Histogram {
+                bounds: &[0.5, 1.0],
+                buckets: vec![1, 2, 1],
+                sum: 3.5,
+                count: 4,
+            },
+        );
+
+    assert_eq!(
+        exposition.finish(),
+        "# HELP zosh_slot The slot of the latest block\n\
+         # TYPE zosh_slot gauge\n\
+         zosh_slot 42\n\
+         # HELP zosh_latency_seconds The latency\n\
+         # TYPE zosh_latency_seconds histogram\n\
+         zosh_latency_seconds_bucket{chain=\"Solana\",le=\"0.5\"} 1\n\
+         zosh_latency_seconds_bucket{chain=\"Solana\",le=\"1\"} 3\n\
+         zosh_latency_seconds_bucket{chain=\"Solana\",le=\"+Inf\"} 4\n\
+         zosh_latency_seconds_sum{chain=\"Solana\"} 3.5\n\
+         zosh_latency_seconds_count{chain=\"Solana\"} 4\n"
+    );
+}
@@ -1,12 +1,21 @@
 //! Subscription handlers for the Spacejam JSON RPC API.
 
+use crate::{
+    types::RpcBridge, BlockInterface, BridgeEvent, BridgeFilter, Encoding, TransactionStatus,
+};
 use anyhow::Result;
 use jsonrpsee::{SubscriptionMessage, SubscriptionSink};
-use serde_json::json;
-use std::sync::Arc;
+use serde_json::value::RawValue;
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    sync::Arc,
+};
 use tokio::sync::Mutex;
 use zcore::Block;
 
+/// The maximum number of tracked transaction status
+const MAX_STATUS: usize = 65_536;
+
 /// The subscription type
 pub type SubscriptionFilter<T> = Arc<Mutex<Vec<(T, SubscriptionSink)>>>;
 
@@ -16,47 +25,196 @@ pub type Subscription = Arc<Mutex<Vec<SubscriptionSink>>>;
 /// Subscription manager
 #[derive(Default, Clone)]
 pub struct SubscriptionManager {
-    /// The best block subscription sinks
-    pub block_sub: Subscription,
+    /// The best block subscription sinks with their encodings
+    pub block_sub: SubscriptionFilter<Encoding>,
+
+    /// The bridge subscription sinks with their filters
+    pub bridge_sub: SubscriptionFilter<BridgeFilter>,
 
     /// The transaction subscription sinks
     pub transaction_sub: SubscriptionFilter<Vec<u8>>,
+
+    /// The latest status of the transactions
+    pub status: Arc<Mutex<StatusCache>>,
+}
+
+/// The latest status of the tracked transactions
+///
+/// The oldest tracked transactions are evicted first once the cache is
+/// full, no matter the status is terminal or not.
+#[derive(Default)]
+pub struct StatusCache {
+    /// The status of the transactions
+    status: HashMap<Vec<u8>, TransactionStatus>,
+
+    /// The transactions in the order of being tracked
+    order: VecDeque<Vec<u8>>,
+}
+
+impl StatusCache {
+    /// Get the status of a transaction
+    pub fn get(&self, txid: &[u8]) -> Option<&TransactionStatus> {
+        self.status.get(txid)
+    }
+
+    /// Update the status of a transaction, evicting the oldest ones
+    pub fn insert(&mut self, txid: Vec<u8>, status: TransactionStatus) {
+        if self.status.insert(txid.clone(), status).is_some() {
+            return;
+        }
+
+        self.order.push_back(txid);
+        while self.order.len() > MAX_STATUS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.status.remove(&oldest);
+            }
+        }
+    }
+
+    /// The number of the tracked transactions
+    pub fn len(&self) -> usize {
+        self.status.len()
+    }
+
+    /// If there is no tracked transaction
+    pub fn is_empty(&self) -> bool {
+        self.status.is_empty()
+    }
 }
 
 impl SubscriptionManager {
     /// Dispatch the best block
     pub async fn dispatch_block(&self, block: &Block) -> Result<()> {
-        let bytes = postcard::to_allocvec(block)?;
-        let raw_value = serde_json::value::to_raw_value(&json!( {
-            "block": bytes,
-        }))?;
-        let mut remove = Vec::new();
-        for sink in self.block_sub.lock().await.iter() {
-            if let Err(e) = sink
-                .send(SubscriptionMessage::from(raw_value.clone()))
-                .await
-            {
-                tracing::warn!("Failed to send block to sink: {e:?}");
-                remove.push(sink.subscription_id());
+        let mut sinks = self.block_sub.lock().await;
+        let mut encoded = HashMap::new();
+        for (encoding, _) in sinks.iter() {
+            if let Entry::Vacant(entry) = encoded.entry(*encoding) {
+                let interface = BlockInterface::new(block, *encoding)?;
+                entry.insert(serde_json::value::to_raw_value(&interface)?);
+            }
+        }
+
+        sinks.retain_mut(|(encoding, sink)| try_send(sink, encoded[encoding].clone(), "block"));
+        Ok(())
+    }
+
+    /// Dispatch the finalized bridge requests to the matching sinks
+    pub async fn dispatch_bridges(&self, block: &Block) -> Result<()> {
+        let mut events = Vec::new();
+        for (hash, bundle) in &block.extrinsic.bridge {
+            for bridge in &bundle.bridge {
+                let raw_value = serde_json::value::to_raw_value(&BridgeEvent {
+                    slot: block.header.slot,
+                    bundle: bs58::encode(hash).into_string(),
+                    bridge: RpcBridge::from(bridge),
+                })?;
+                events.push((bridge, raw_value));
             }
         }
 
-        for id in remove {
-            self.block_sub
-                .lock()
-                .await
-                .retain(|sink| sink.subscription_id() != id);
+        self.bridge_sub.lock().await.retain_mut(|(filter, sink)| {
+            events
+                .iter()
+                .filter(|(bridge, _)| filter.matches(bridge))
+                .all(|(_, raw_value)| try_send(sink, raw_value.clone(), "bridge"))
+        });
+        Ok(())
+    }
+
+    /// Dispatch the status of a transaction
+    ///
+    /// The sinks are dropped once the status is terminal.
+    pub async fn dispatch_transaction(&self, txid: &[u8], status: TransactionStatus) -> Result<()> {
+        let raw_value = serde_json::value::to_raw_value(&status)?;
+        self.status
+            .lock()
+            .await
+            .insert(txid.to_vec(), status.clone());
+
+        self.transaction_sub.lock().await.retain_mut(|(id, sink)| {
+            if id.as_slice() != txid {
+                return true;
+            }
+
+            try_send(sink, raw_value.clone(), "transaction status") && !status.is_terminal()
+        });
+        Ok(())
+    }
+
+    /// Subscribe to the best blocks in the encoding
+    pub async fn subscribe_block(&self, encoding: Encoding, sink: SubscriptionSink) {
+        park(&self.block_sub, encoding, sink).await;
+    }
+
+    /// Subscribe to the finalized bridge requests matching the filter
+    pub async fn subscribe_bridges(&self, filter: BridgeFilter, sink: SubscriptionSink) {
+        park(&self.bridge_sub, filter, sink).await;
+    }
+
+    /// Subscribe to the status of a transaction
+    ///
+    /// The current status is sent immediately if any, the sink is only
+    /// parked if the transaction has not reached a terminal state yet.
+    pub async fn subscribe_transaction(
+        &self,
+        txid: Vec<u8>,
+        mut sink: SubscriptionSink,
+    ) -> Result<()> {
+        let tracked = self.status.lock().await;
+        if let Some(status) = tracked.get(&txid) {
+            let raw_value = serde_json::value::to_raw_value(status)?;
+            if !try_send(&mut sink, raw_value, "transaction status") || status.is_terminal() {
+                return Ok(());
+            }
         }
+
+        park(&self.transaction_sub, txid, sink).await;
         Ok(())
     }
+}
+
+/// Send the message to the sink without waiting
+///
+/// The messages are sent under the locks of the subscriptions, so the slow
+/// subscribers never block the dispatching or the new subscriptions. Returns
+/// false if the sink is full or closed, the caller drops it.
+fn try_send(sink: &mut SubscriptionSink, raw_value: Box<RawValue>, kind: &str) -> bool {
+    match sink.try_send(SubscriptionMessage::from(raw_value)) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Dropping sink failed to receive {kind}: {e:?}");
+            false
+        }
+    }
+}
+
+/// Park the sink, dropping the closed ones
+///
+/// The sinks hold the subscription slots of their connections until they
+/// are dropped, the unsubscribed ones are only dropped here or on failed
+/// sends.
+async fn park<T>(subscription: &SubscriptionFilter<T>, filter: T, sink: SubscriptionSink) {
+    let mut sinks = subscription.lock().await;
+    sinks.retain(|(_, sink)| !sink.is_closed());
+    sinks.push((filter, sink));
+}
+
+#[test]
+fn test_status_cache_eviction() {
+    let mut cache = StatusCache::default();
+    for i in 0..MAX_STATUS as u32 {
+        cache.insert(i.to_le_bytes().to_vec(), TransactionStatus::Queued);
+    }
+
+    // updating a tracked transaction keeps its order
+    let first = 0u32.to_le_bytes().to_vec();
+    cache.insert(first.clone(), TransactionStatus::Queued);
+    assert_eq!(cache.len(), MAX_STATUS);
 
-    // /// Dispatch the transaction status
-    // pub async fn dispatch_transaction(&self, txid: &Vec<u8>) -> Result<()> {
-    //     let raw_value = serde_json::value::to_raw_value(&txid)?;
-    //     for sink in self.transaction_sub.lock().await.iter() {
-    //         sink.send(SubscriptionMessage::from(raw_value.clone()))
-    //             .await?;
-    //     }
-    //     Ok(())
-    // }
+    // the oldest non-terminal transaction is evicted
+    let last = (MAX_STATUS as u32).to_le_bytes().to_vec();
+    cache.insert(last.clone(), TransactionStatus::Queued);
+    assert_eq!(cache.len(), MAX_STATUS);
+    assert!(cache.get(&first).is_none());
+    assert!(cache.get(&last).is_some());
 }
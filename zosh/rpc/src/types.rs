@@ -0,0 +1,588 @@
+//! JSON types of the Zosh RPC API.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, str::FromStr};
+use zcore::{
+    bft::Bft,
+    ex::{Bridge, BridgeBundle, Receipt},
+    registry::Chain,
+    Block, Hash, Header, InclusionProof,
+};
+
+/// The encoding of the block payloads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// The human-readable JSON block
+    #[default]
+    Json,
+
+    /// The canonical encoding in base64
+    Base64,
+
+    /// The canonical encoding in base58
+    Base58,
+}
+
+/// The identifier of a block, either the slot or the base58 hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BlockId {
+    /// The slot of the block
+    Slot(u32),
+
+    /// The base58 encoded hash of the block
+    Hash(String),
+}
+
+impl FromStr for BlockId {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse() {
+            Ok(slot) => Self::Slot(slot),
+            Err(_) => Self::Hash(s.into()),
+        })
+    }
+}
+
+/// The block in JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcBlock {
+    /// The header of the block
+    pub header: RpcHeader,
+
+    /// The bridge bundles of the block
+    pub bundles: Vec<RpcBundle>,
+
+    /// The receipts of the block
+    pub receipts: Vec<RpcReceipt>,
+}
+
+impl From<&Block> for RpcBlock {
+    fn from(block: &Block) -> Self {
+        Self {
+            header: RpcHeader::from(&block.header),
+            bundles: block
+                .extrinsic
+                .bridge
+                .iter()
+                .map(|(hash, bundle)| RpcBundle::new(hash, bundle))
+                .collect(),
+            receipts: block.extrinsic.receipts.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// The block header in JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcHeader {
+    /// The version of the header
+    pub version: u8,
+
+    /// The slot of the block
+    pub slot: u32,
+
+    /// The hash of the block
+    pub hash: String,
+
+    /// The parent block hash
+    pub parent: String,
+
+    /// The merkle root of the parent state
+    pub state: String,
+
+    /// The accumulator of the block
+    pub accumulator: String,
+
+    /// The merkle root of the extrinsic
+    pub extrinsic: String,
+
+    /// The votes of the validators
+    pub votes: BTreeMap<String, String>,
+}
+
+impl From<&Header> for RpcHeader {
+    fn from(header: &Header) -> Self {
+        Self {
+            version: header.version,
+            slot: header.slot,
+            hash: bs58::encode(header.hash()).into_string(),
+            parent: bs58::encode(header.parent).into_string(),
+            state: bs58::encode(header.state).into_string(),
+            accumulator: bs58::encode(header.accumulator).into_string(),
+            extrinsic: bs58::encode(header.extrinsic).into_string(),
+            votes: header
+                .votes
+                .iter()
+                .map(|(k, v)| (bs58::encode(k).into_string(), bs58::encode(v).into_string()))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<&RpcHeader> for Header {
+    type Error = anyhow::Error;
+
+    fn try_from(header: &RpcHeader) -> Result<Self> {
+        let mut votes = BTreeMap::new();
+        for (key, sig) in &header.votes {
+            votes.insert(decode_hash(key)?, bs58::decode(sig).into_vec()?);
+        }
+
+        let decoded = Self {
+            version: header.version,
+            slot: header.slot,
+            parent: decode_hash(&header.parent)?,
+            state: decode_hash(&header.state)?,
+            accumulator: decode_hash(&header.accumulator)?,
+            extrinsic: decode_hash(&header.extrinsic)?,
+            votes,
+        };
+        if bs58::encode(decoded.hash()).into_string() != header.hash {
+            anyhow::bail!("Header hash mismatch at slot {}", header.slot);
+        }
+        Ok(decoded)
+    }
+}
+
+/// The bridge bundle in JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcBundle {
+    /// The hash of the bundle
+    pub hash: String,
+
+    /// The target chain of the bundle
+    pub target: String,
+
+    /// The bridge requests of the bundle
+    pub bridges: Vec<RpcBridge>,
+
+    /// The data of the outer transaction
+    pub data: String,
+
+    /// The signatures of the outer transaction
+    pub signatures: Vec<String>,
+}
+
+impl RpcBundle {
+    /// Create the JSON bundle from the bundle and its hash
+    pub fn new(hash: &Hash, bundle: &BridgeBundle) -> Self {
+        Self {
+            hash: bs58::encode(hash).into_string(),
+            target: format!("{:?}", bundle.target),
+            bridges: bundle.bridge.iter().map(Into::into).collect(),
+            data: bs58::encode(&bundle.data).into_string(),
+            signatures: bundle
+                .signatures
+                .iter()
+                .map(|s| bs58::encode(s).into_string())
+                .collect(),
+        }
+    }
+}
+
+/// The bridge request in JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcBridge {
+    /// The coin of the bridge
+    pub coin: String,
+
+    /// The recipient address
+    pub recipient: String,
+
+    /// The gross amount
+    pub amount: u64,
+
+    /// The bridge fee
+    pub fee: u64,
+
+    /// The source chain
+    pub source: String,
+
+    /// The target chain
+    pub target: String,
+
+    /// The source txid
+    pub txid: String,
+}
+
+impl From<&Bridge> for RpcBridge {
+    fn from(bridge: &Bridge) -> Self {
+        Self {
+            coin: format!("{:?}", bridge.coin),
+            recipient: encode_recipient(&bridge.recipient),
+            amount: bridge.amount,
+            fee: bridge.fee,
+            source: format!("{:?}", bridge.source),
+            target: format!("{:?}", bridge.target),
+            txid: encode_txid(&bridge.txid),
+        }
+    }
+}
+
+impl TryFrom<&RpcBridge> for Bridge {
+    type Error = anyhow::Error;
+
+    fn try_from(bridge: &RpcBridge) -> Result<Self> {
+        let target = parse(&bridge.target)?;
+        Ok(Self {
+            coin: parse(&bridge.coin)?,
+            recipient: decode_recipient(&bridge.recipient, target)?,
+            amount: bridge.amount,
+            fee: bridge.fee,
+            source: parse(&bridge.source)?,
+            target,
+            txid: decode_txid(&bridge.txid)?,
+        })
+    }
+}
+
+/// The filter of the bridge subscriptions
+///
+/// Unset fields match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BridgeFilter {
+    /// The recipient address, base58 for solana and the address for zcash
+    pub recipient: Option<String>,
+
+    /// The source chain
+    pub source: Option<Chain>,
+
+    /// The target chain
+    pub target: Option<Chain>,
+
+    /// The minimum gross amount
+    pub min_amount: Option<u64>,
+}
+
+impl BridgeFilter {
+    /// Check if the bridge request matches the filter
+    pub fn matches(&self, bridge: &Bridge) -> bool {
+        self.recipient
+            .as_ref()
+            .is_none_or(|recipient| *recipient == encode_recipient(&bridge.recipient))
+            && self.source.is_none_or(|source| source == bridge.source)
+            && self.target.is_none_or(|target| target == bridge.target)
+            && self.min_amount.is_none_or(|min| bridge.amount >= min)
+    }
+}
+
+/// The bridge request finalized in a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeEvent {
+    /// The slot of the block
+    pub slot: u32,
+
+    /// The base58 hash of the bundle
+    pub bundle: String,
+
+    /// The bridge request
+    pub bridge: RpcBridge,
+}
+
+/// The receipt in JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcReceipt {
+    /// The source txid of the bridge request
+    pub anchor: String,
+
+    /// The coin of the bridge
+    pub coin: String,
+
+    /// The net amount delivered
+    pub amount: u64,
+
+    /// The target txid
+    pub txid: String,
+
+    /// The source chain
+    pub source: String,
+
+    /// The target chain
+    pub target: String,
+}
+
+impl From<&Receipt> for RpcReceipt {
+    fn from(receipt: &Receipt) -> Self {
+        Self {
+            anchor: encode_txid(&receipt.anchor),
+            coin: format!("{:?}", receipt.coin),
+            amount: receipt.amount,
+            txid: encode_txid(&receipt.txid),
+            source: format!("{:?}", receipt.source),
+            target: format!("{:?}", receipt.target),
+        }
+    }
+}
+
+/// The status of the mempool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PoolStatus {
+    /// The bundles aggregating signatures
+    pub in_progress: usize,
+
+    /// The bundles ready for packing
+    pub completed: usize,
+
+    /// The pending receipts
+    pub receipts: usize,
+}
+
+/// The acknowledgement of a submitted bridge request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitAck {
+    /// The base58 hash of the queued bridge request
+    pub id: String,
+
+    /// The source txid of the bridge request
+    pub txid: String,
+
+    /// The bridge fee charged by the chain state
+    pub fee: u64,
+}
+
+/// The validator set in JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Validators {
+    /// The base58 encoded ed25519 public keys
+    pub validators: Vec<String>,
+
+    /// The number of votes required for a block
+    pub threshold: u8,
+}
+
+impl From<&Bft> for Validators {
+    fn from(bft: &Bft) -> Self {
+        Self {
+            validators: bft
+                .validators
+                .iter()
+                .map(|v| bs58::encode(v).into_string())
+                .collect(),
+            threshold: bft.threshold,
+        }
+    }
+}
+
+/// The inclusion proof of a transaction in JSON
+///
+/// The txid and the merkle path are the raw bytes in base58.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcInclusionProof {
+    /// The finalized header with its votes
+    pub header: RpcHeader,
+
+    /// The txid of the transaction
+    pub txid: String,
+
+    /// The index of the txid in the sorted transactions of the block
+    pub index: u32,
+
+    /// The number of the transactions in the block
+    pub count: u32,
+
+    /// The merkle path from the txid to the extrinsic root
+    pub path: Vec<String>,
+}
+
+impl From<&InclusionProof> for RpcInclusionProof {
+    fn from(proof: &InclusionProof) -> Self {
+        Self {
+            header: RpcHeader::from(&proof.header),
+            txid: bs58::encode(&proof.txid).into_string(),
+            index: proof.index,
+            count: proof.count,
+            path: proof
+                .path
+                .iter()
+                .map(|node| bs58::encode(node).into_string())
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<&RpcInclusionProof> for InclusionProof {
+    type Error = anyhow::Error;
+
+    fn try_from(proof: &RpcInclusionProof) -> Result<Self> {
+        Ok(Self {
+            header: Header::try_from(&proof.header)?,
+            txid: bs58::decode(&proof.txid).into_vec()?,
+            index: proof.index,
+            count: proof.count,
+            path: proof
+                .path
+                .iter()
+                .map(|node| bs58::decode(node).into_vec())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Encode the txid in the display format of its chain
+///
+/// Zcash txids (32 bytes) are reversed hex, solana signatures (64 bytes)
+/// and the other values are base58.
+pub fn encode_txid(txid: &[u8]) -> String {
+    if txid.len() != 32 {
+        return bs58::encode(txid).into_string();
+    }
+
+    let mut bytes = txid.to_vec();
+    bytes.reverse();
+    hex::encode(bytes)
+}
+
+/// Decode the txid from its display format
+///
+/// The format is chosen by the decoded length, only the hex of 32 bytes is
+/// a zcash txid, so the base58 made of hex characters is still base58.
+pub fn decode_txid(txid: &str) -> Result<Vec<u8>> {
+    match hex::decode(txid) {
+        Ok(mut bytes) if bytes.len() == 32 => {
+            bytes.reverse();
+            Ok(bytes)
+        }
+        _ => bs58::decode(txid)
+            .into_vec()
+            .map_err(|_| anyhow::anyhow!("Invalid txid {txid}: must be hex or base58")),
+    }
+}
+
+/// Decode the base58 hash
+fn decode_hash(hash: &str) -> Result<Hash> {
+    bs58::decode(hash)
+        .into_vec()?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid hash {hash}"))
+}
+
+/// Parse the chain or the coin from its variant name
+fn parse<T: serde::de::DeserializeOwned>(name: &str) -> Result<T> {
+    serde_json::from_value(serde_json::Value::String(name.into()))
+        .map_err(|_| anyhow::anyhow!("Invalid variant {name}"))
+}
+
+/// Decode the recipient address of the target chain
+fn decode_recipient(recipient: &str, target: Chain) -> Result<Vec<u8>> {
+    match target {
+        Chain::Solana => bs58::decode(recipient)
+            .into_vec()
+            .map_err(|_| anyhow::anyhow!("Invalid solana recipient {recipient}")),
+        Chain::Zcash => Ok(recipient.as_bytes().to_vec()),
+    }
+}
+
+/// Encode the recipient address
+fn encode_recipient(recipient: &[u8]) -> String {
+    if recipient.len() == 32 {
+        bs58::encode(recipient).into_string()
+    } else {
+        String::from_utf8(recipient.to_vec()).unwrap_or_else(|_| hex::encode(recipient))
+    }
+}
+
+#[test]
+fn test_bridge_filter() {
+    let bridge = Bridge {
+        coin: zcore::registry::Coin::Zec,
+        recipient: vec![1; 32],
+        amount: 100,
+        fee: 1,
+        source: Chain::Zcash,
+        target: Chain::Solana,
+        txid: vec![2; 32],
+    };
+    assert!(BridgeFilter::default().matches(&bridge));
+
+    let filter = BridgeFilter {
+        recipient: Some(bs58::encode([1; 32]).into_string()),
+        source: Some(Chain::Zcash),
+        target: Some(Chain::Solana),
+        min_amount: Some(100),
+    };
+    assert!(filter.matches(&bridge));
+
+    for filter in [
+        BridgeFilter {
+            recipient: Some(bs58::encode([3; 32]).into_string()),
+            ..Default::default()
+        },
+        BridgeFilter {
+            target: Some(Chain::Zcash),
+            ..Default::default()
+        },
+        BridgeFilter {
+            min_amount: Some(101),
+            ..Default::default()
+        },
+    ] {
+        assert!(!filter.matches(&bridge));
+    }
+}
+
+#[test]
+fn test_inclusion_proof_roundtrip() -> Result<()> {
+    let mut header = Header {
+        slot: 7,
+        extrinsic: [1; 32],
+        ..Default::default()
+    };
+    header.votes.insert([2; 32], vec![3; 64]);
+
+    let proof = InclusionProof {
+        header,
+        txid: vec![1; 32],
+        index: 0,
+        count: 1,
+        path: Vec::new(),
+    };
+    let decoded = InclusionProof::try_from(&RpcInclusionProof::from(&proof))?;
+    assert_eq!(decoded.header.hash(), proof.header.hash());
+    assert_eq!(decoded.header.votes, proof.header.votes);
+    assert_eq!(decoded.txid, proof.txid);
+    Ok(())
+}
+
+#[test]
+fn test_bridge_roundtrip() -> Result<()> {
+    let mut bridge = Bridge {
+        coin: zcore::registry::Coin::Zec,
+        recipient: vec![1; 32],
+        amount: 100,
+        fee: 1,
+        source: Chain::Zcash,
+        target: Chain::Solana,
+        txid: vec![2; 32],
+    };
+    assert_eq!(Bridge::try_from(&RpcBridge::from(&bridge))?, bridge);
+
+    bridge.recipient = b"utest1recipient".to_vec();
+    bridge.source = Chain::Solana;
+    bridge.target = Chain::Zcash;
+    bridge.txid = vec![3; 64];
+    assert_eq!(Bridge::try_from(&RpcBridge::from(&bridge))?, bridge);
+
+    let mut invalid = RpcBridge::from(&bridge);
+    invalid.target = "Ethereum".into();
+    assert!(Bridge::try_from(&invalid).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_txid_roundtrip() -> Result<()> {
+    for txid in [vec![1; 32], vec![2; 64], vec![3; 96], vec![4; 20]] {
+        assert_eq!(decode_txid(&encode_txid(&txid))?, txid);
+    }
+
+    // the zcash txids are displayed in the reversed byte order
+    let zcash = (0..32).collect::<Vec<u8>>();
+    assert!(encode_txid(&zcash).starts_with("1f1e"));
+
+    // the base58 made of hex characters is not decoded as hex
+    assert_eq!(decode_txid("abcd")?, bs58::decode("abcd").into_vec()?);
+    assert!(decode_txid("0OIl").is_err());
+    Ok(())
+}
@@ -2,30 +2,40 @@
 
 use crate::{Config, Runtime, Storage};
 use anyhow::Result;
-use crypto::merkle;
-use zcore::{Block, Hash, Header};
+use zcore::{Block, Extrinsic, Hash, Header, HEADER_VERSION};
 
 impl<C: Config> Runtime<C> {
     /// Author an unauthorized block
     pub async fn author(&mut self) -> Result<Block> {
         let state = self.storage.state()?;
-        let parent = state.present;
 
-        // get the extrinsic from the pool
-        let extrinsic = self.pool.lock().await.pack()?;
+        // get the extrinsic from the pool, skipping the included ones
+        let extrinsic = self.pool.lock().await.pack(
+            &state.limits,
+            |txid| self.storage.exists(txid),
+            |anchor| Ok(self.storage.receipt(anchor)?.is_some()),
+        )?;
+        self.build(extrinsic)
+    }
+
+    /// Build an unauthorized block of the extrinsic on top of the present head
+    pub fn build(&self, extrinsic: Extrinsic) -> Result<Block> {
+        let state = self.storage.state()?;
+        let parent = state.present;
         let txs = extrinsic.txs();
         let accumulator = self.accumulate(state.accumulator, &txs)?;
         let state = self.storage.root()?;
 
         // Build the header first
         let header = Header {
+            version: HEADER_VERSION,
             // TODO: if the previous lead failed to author lock, we need to
             // skip the slot and use the next slot.
             slot: parent.slot + 1,
             parent: parent.hash,
             state,
             accumulator,
-            extrinsic: merkle::root(txs),
+            extrinsic: extrinsic.root(),
             votes: Default::default(),
         };
 
@@ -2,16 +2,159 @@
 
 use anyhow::Result;
 use core::future::Future;
-use zcore::Block;
+use zcore::{
+    bft::Bft,
+    ex::{Bridge, BridgeBundle, Receipt},
+    Block,
+};
 
 /// The hook for the runtime
+///
+/// All events are no-op by default, hooks can be chained with tuples,
+/// e.g. `(UIHook, RpcHook, MetricsHook)`, the members of a tuple fail
+/// independently, the errors are logged without interrupting the others.
 pub trait Hook: Clone {
-    /// The hook for the runtime
-    fn on_block_finalized(&self, block: &Block) -> impl Future<Output = Result<()>>;
+    /// Called when a block is imported
+    fn on_block_imported(&self, _block: &Block) -> impl Future<Output = Result<()>> {
+        async { Ok(()) }
+    }
+
+    /// Called when a block is finalized
+    fn on_block_finalized(&self, _block: &Block) -> impl Future<Output = Result<()>> {
+        async { Ok(()) }
+    }
+
+    /// Called when a bridge request is validated and queued for bundling
+    fn on_bridge_queued(&self, _bridge: &Bridge) -> impl Future<Output = Result<()>> {
+        async { Ok(()) }
+    }
+
+    /// Called when a bridge request is rejected
+    fn on_bridge_failed(
+        &self,
+        _bridge: &Bridge,
+        _reason: &str,
+    ) -> impl Future<Output = Result<()>> {
+        async { Ok(()) }
+    }
+
+    /// Called when a bridge bundle is queued into the pool
+    fn on_bundle_queued(&self, _bundle: &BridgeBundle) -> impl Future<Output = Result<()>> {
+        async { Ok(()) }
+    }
+
+    /// Called when a bridge bundle reaches the signature threshold
+    fn on_bundle_signed(&self, _bundle: &BridgeBundle) -> impl Future<Output = Result<()>> {
+        async { Ok(()) }
+    }
+
+    /// Called when a receipt is produced
+    fn on_receipt_produced(&self, _receipt: &Receipt) -> impl Future<Output = Result<()>> {
+        async { Ok(()) }
+    }
+
+    /// Called when the validator set is changed
+    fn on_validators_changed(&self, _bft: &Bft) -> impl Future<Output = Result<()>> {
+        async { Ok(()) }
+    }
+}
+
+impl Hook for () {}
+
+/// Implement the hook for tuples of hooks
+macro_rules! impl_hook_tuple {
+    ($($name:ident: $idx:tt),+) => {
+        impl<$($name: Hook),+> Hook for ($($name,)+) {
+            async fn on_block_imported(&self, block: &Block) -> Result<()> {
+                $(log("on_block_imported", self.$idx.on_block_imported(block).await);)+
+                Ok(())
+            }
+
+            async fn on_block_finalized(&self, block: &Block) -> Result<()> {
+                $(log("on_block_finalized", self.$idx.on_block_finalized(block).await);)+
+                Ok(())
+            }
+
+            async fn on_bridge_queued(&self, bridge: &Bridge) -> Result<()> {
+                $(log("on_bridge_queued", self.$idx.on_bridge_queued(bridge).await);)+
+                Ok(())
+            }
+
+            async fn on_bridge_failed(&self, bridge: &Bridge, reason: &str) -> Result<()> {
+                $(log("on_bridge_failed", self.$idx.on_bridge_failed(bridge, reason).await);)+
+                Ok(())
+            }
+
+            async fn on_bundle_queued(&self, bundle: &BridgeBundle) -> Result<()> {
+                $(log("on_bundle_queued", self.$idx.on_bundle_queued(bundle).await);)+
+                Ok(())
+            }
+
+            async fn on_bundle_signed(&self, bundle: &BridgeBundle) -> Result<()> {
+                $(log("on_bundle_signed", self.$idx.on_bundle_signed(bundle).await);)+
+                Ok(())
+            }
+
+            async fn on_receipt_produced(&self, receipt: &Receipt) -> Result<()> {
+                $(log("on_receipt_produced", self.$idx.on_receipt_produced(receipt).await);)+
+                Ok(())
+            }
+
+            async fn on_validators_changed(&self, bft: &Bft) -> Result<()> {
+                $(log("on_validators_changed", self.$idx.on_validators_changed(bft).await);)+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_hook_tuple!(A: 0);
+impl_hook_tuple!(A: 0, B: 1);
+impl_hook_tuple!(A: 0, B: 1, C: 2);
+impl_hook_tuple!(A: 0, B: 1, C: 2, D: 3);
+impl_hook_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4);
+
+/// Log the error of a hook without interrupting the others
+fn log(event: &str, result: Result<()>) {
+    if let Err(e) = result {
+        tracing::error!("Hook {event} failed: {e:?}");
+    }
 }
 
-impl Hook for () {
-    async fn on_block_finalized(&self, _block: &Block) -> Result<()> {
-        Ok(())
+#[tokio::test]
+async fn test_hook_tuple() -> Result<()> {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[derive(Clone, Default)]
+    struct Counter(Arc<AtomicUsize>);
+
+    impl Hook for Counter {
+        async fn on_block_finalized(&self, _block: &Block) -> Result<()> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
     }
+
+    #[derive(Clone)]
+    struct Failing;
+
+    impl Hook for Failing {
+        async fn on_block_finalized(&self, _block: &Block) -> Result<()> {
+            anyhow::bail!("failing hook")
+        }
+    }
+
+    // the failing member never interrupts the others
+    let counter = Counter::default();
+    let hook = (Failing, counter.clone(), ((), counter.clone()));
+    hook.on_block_finalized(&Block::default()).await?;
+    assert_eq!(counter.0.load(Ordering::Relaxed), 2);
+
+    // the events not implemented by the members are no-op
+    hook.on_block_imported(&Block::default()).await?;
+    assert_eq!(counter.0.load(Ordering::Relaxed), 2);
+    Ok(())
 }
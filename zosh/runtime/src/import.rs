@@ -2,7 +2,12 @@
 
 use crate::{storage::Commit, Config, Runtime, Storage};
 use anyhow::Result;
-use zcore::{state::key, Block};
+use std::collections::BTreeSet;
+use zcore::{
+    ex::Extrinsic,
+    state::{self, key, FeeSchedule, FeeTotals},
+    Block, HEADER_VERSION,
+};
 
 impl<C: Config> Runtime<C> {
     /// Import a new block
@@ -14,13 +19,24 @@ impl<C: Config> Runtime<C> {
     ///
     /// TODO: but we need to validate the rotation of the validators here.
     pub fn import(&mut self, block: &Block) -> Result<()> {
+        if block.header.version != HEADER_VERSION {
+            anyhow::bail!(
+                "Unsupported header version: expected={HEADER_VERSION}, got={}",
+                block.header.version
+            );
+        }
+
         let state = self.storage.state()?;
         state.bft.validate_votes(&block.header)?;
 
-        // 1. validate the parent state root
+        // 1. validate the parent state root, the extrinsic root and the block size
         if block.header.state != self.storage.root()? {
             anyhow::bail!("Invalid parent state root");
         }
+        if block.header.extrinsic != block.extrinsic.root() {
+            anyhow::bail!("Invalid extrinsic root");
+        }
+        state.limits.validate(&block.extrinsic)?;
 
         // 2. update the accumulator with the signatures of the block
         let txs = block.extrinsic.txs();
@@ -34,15 +50,193 @@ impl<C: Config> Runtime<C> {
             );
         }
 
-        // 3. stores the block to the storage
+        // 3. reject the bridge requests and the receipts included twice
+        ensure_new(
+            &block.extrinsic,
+            |txid| self.storage.exists(txid),
+            |anchor| Ok(self.storage.receipt(anchor)?.is_some()),
+        )?;
+
+        // 4. validate the bridge fees and accumulate the collected fees
+        let collected = collect_fees(&state.fee, &state.collected, &block.extrinsic)?;
+
+        // 5. stores the block to the storage
         let head = block.header.head();
         let mut commit = Commit::default();
         commit.insert(key::ACCUMULATOR_KEY, crypto::blake3(&accumulator).to_vec());
         commit.insert(key::BFT_KEY, postcard::to_allocvec(&state.bft)?);
         commit.insert(key::PRESENT_KEY, postcard::to_allocvec(&head)?);
+        commit.insert(key::COLLECTED_KEY, postcard::to_allocvec(&collected)?);
         self.storage.commit(commit)?;
         self.storage.set_block(block)?;
-        self.storage.set_txs(txs)?;
+        self.storage.set_txs(block.header.slot, txs)?;
+        self.storage
+            .set_bridges(block.extrinsic.bridges().cloned().collect())?;
+        self.storage
+            .set_receipts(block.extrinsic.receipts.clone())?;
         Ok(())
     }
 }
+
+/// Validate the bridge fees against the schedule and accumulate them
+///
+/// NOTE: the schedule is only set at genesis, changing it would need the
+/// bridge requests to record the version of the schedule charged.
+fn collect_fees(
+    schedule: &FeeSchedule,
+    collected: &FeeTotals,
+    extrinsic: &Extrinsic,
+) -> Result<FeeTotals> {
+    let mut collected = collected.clone();
+    for bridge in extrinsic.bridges() {
+        let fee = schedule.get(bridge.target).charge(bridge.amount)?;
+        if fee != bridge.fee {
+            anyhow::bail!(
+                "Invalid bridge fee: txid={}, expected={fee}, got={}",
+                bs58::encode(&bridge.txid).into_string(),
+                bridge.fee
+            );
+        }
+        state::collect(&mut collected, bridge.target, fee)?;
+    }
+
+    Ok(collected)
+}
+
+/// Ensure the bridge requests and the receipts of the extrinsic are not
+/// included yet
+///
+/// The bundles rebuilt after the failed transactions pay the included
+/// bridge requests again, they are never imported twice. A bridge request
+/// has at most one receipt, keyed by its anchor.
+fn ensure_new(
+    extrinsic: &Extrinsic,
+    exists: impl Fn(&[u8]) -> Result<bool>,
+    receipted: impl Fn(&[u8]) -> Result<bool>,
+) -> Result<()> {
+    let mut included = BTreeSet::new();
+    for bridge in extrinsic.bridges() {
+        if !included.insert(&bridge.txid) || exists(&bridge.txid)? {
+            anyhow::bail!(
+                "Duplicate bridge request: txid={}",
+                bs58::encode(&bridge.txid).into_string()
+            );
+        }
+    }
+
+    let mut anchors = BTreeSet::new();
+    for receipt in &extrinsic.receipts {
+        if !anchors.insert(&receipt.anchor) || receipted(&receipt.anchor)? {
+            anyhow::bail!(
+                "Duplicate receipt: anchor={}",
+                bs58::encode(&receipt.anchor).into_string()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_ensure_new() -> Result<()> {
+    use zcore::{
+        ex::{Bridge, BridgeBundle, Receipt},
+        registry::{Chain, Coin},
+    };
+
+    let bridge = |txid: u8| Bridge {
+        coin: Coin::Zec,
+        recipient: vec![1; 32],
+        amount: 100_000,
+        fee: 10_000,
+        source: Chain::Solana,
+        target: Chain::Zcash,
+        txid: vec![txid; 64],
+    };
+    let bundle = |bridges: Vec<Bridge>| BridgeBundle {
+        target: Chain::Zcash,
+        bridge: bridges,
+        data: Vec::new(),
+        signatures: Vec::new(),
+    };
+
+    let mut extrinsic = Extrinsic::default();
+    let first = bundle(vec![bridge(1), bridge(2)]);
+    extrinsic.bridge.insert(first.hash()?, first);
+    ensure_new(&extrinsic, |_| Ok(false), |_| Ok(false))?;
+
+    // the bridge requests included in the previous blocks
+    assert!(ensure_new(&extrinsic, |txid| Ok(txid[0] == 2), |_| Ok(false)).is_err());
+
+    // the rebuilt bundle in the same block
+    let rebuilt = bundle(vec![bridge(2)]);
+    extrinsic.bridge.insert(rebuilt.hash()?, rebuilt);
+    assert!(ensure_new(&extrinsic, |_| Ok(false), |_| Ok(false)).is_err());
+
+    // the receipts of one bundle share the txid with different anchors
+    let receipt = |anchor: u8| Receipt {
+        anchor: vec![anchor; 64],
+        coin: Coin::Zec,
+        amount: 90_000,
+        txid: vec![9; 32],
+        source: Chain::Solana,
+        target: Chain::Zcash,
+    };
+    let mut extrinsic = Extrinsic::default();
+    extrinsic.receipts = vec![receipt(1), receipt(2)];
+    ensure_new(&extrinsic, |_| Ok(false), |_| Ok(false))?;
+    let txs = extrinsic.txs();
+    assert_ne!(txs[0], txs[1]);
+
+    // the anchors with receipts in the previous blocks
+    assert!(ensure_new(&extrinsic, |_| Ok(false), |anchor| Ok(anchor[0] == 2)).is_err());
+
+    // the same anchor twice in the block
+    extrinsic.receipts.push(receipt(1));
+    assert!(ensure_new(&extrinsic, |_| Ok(false), |_| Ok(false)).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_collect_fees() -> Result<()> {
+    use zcore::{
+        ex::{Bridge, BridgeBundle},
+        registry::{Chain, Coin},
+        state::Fee,
+    };
+
+    let schedule = FeeSchedule {
+        zcash: Fee {
+            flat: 1_000,
+            bps: 0,
+            min: 10_000,
+        },
+        ..Default::default()
+    };
+    let mut bridge = Bridge {
+        coin: Coin::Zec,
+        recipient: vec![1; 32],
+        amount: 100_000,
+        fee: 10_000,
+        source: Chain::Solana,
+        target: Chain::Zcash,
+        txid: vec![2; 64],
+    };
+    let extrinsic = |bridge: &Bridge| -> Result<Extrinsic> {
+        let mut bundle = BridgeBundle::new(Chain::Zcash);
+        bundle.bridge.push(bridge.clone());
+        let mut extrinsic = Extrinsic::default();
+        extrinsic.bridge.insert(bundle.hash()?, bundle);
+        Ok(extrinsic)
+    };
+
+    let collected = collect_fees(&schedule, &FeeTotals::default(), &extrinsic(&bridge)?)?;
+    assert_eq!(collected[&Chain::Zcash], 10_000);
+
+    // the fees off the schedule are rejected
+    for fee in [0, 9_999, 99_999] {
+        bridge.fee = fee;
+        assert!(collect_fees(&schedule, &collected, &extrinsic(&bridge)?).is_err());
+    }
+    Ok(())
+}
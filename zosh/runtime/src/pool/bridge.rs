@@ -1,8 +1,8 @@
 //! The bridge requests of the zosh
 
 use anyhow::Result;
-use std::collections::BTreeMap;
-use zcore::{ex::BridgeBundle, Hash};
+use std::collections::{BTreeMap, BTreeSet};
+use zcore::{ex::BridgeBundle, registry::Chain, state::Limits, Hash};
 
 /// The bridge requests pool for zosh
 #[derive(Default)]
@@ -27,22 +27,85 @@ impl BridgePool {
         }
     }
 
+    /// The number of bundles aggregating signatures
+    pub fn in_progress(&self) -> usize {
+        self.in_progress.len()
+    }
+
+    /// The number of bundles ready for packing
+    pub fn completed(&self) -> usize {
+        self.completed.len()
+    }
+
     /// Queue a bridge bundle
-    pub fn dev_pack(&mut self, bundles: Vec<BridgeBundle>) -> Result<()> {
+    ///
+    /// Returns the rejected bundles with the reasons, the ones exceeding
+    /// the block limits or spending the notes of another pooled bundle.
+    pub fn dev_pack(
+        &mut self,
+        bundles: Vec<BridgeBundle>,
+        limits: &Limits,
+    ) -> Result<Vec<(BridgeBundle, String)>> {
+        let mut rejected = Vec::new();
         for bundle in bundles {
             let hash = bundle.hash()?;
+            if let Err(e) = limits.validate_bundle(&bundle) {
+                rejected.push((bundle, e.to_string()));
+                continue;
+            }
+
+            if let Some(pooled) = self.conflict(&hash, &bundle) {
+                let reason = format!(
+                    "Spending the notes of bundle {}",
+                    bs58::encode(pooled).into_string()
+                );
+                rejected.push((bundle, reason));
+                continue;
+            }
+
             self.completed.insert(hash, bundle);
         }
-        Ok(())
+        Ok(rejected)
     }
 
     /// Queue a bridge bundle
-    pub fn queue(&mut self, bundles: Vec<BridgeBundle>) -> Result<()> {
+    ///
+    /// Returns the rejected bundles with the reasons, the ones exceeding
+    /// the block limits or spending the notes of another pooled bundle.
+    pub fn queue(
+        &mut self,
+        bundles: Vec<BridgeBundle>,
+        limits: &Limits,
+    ) -> Result<Vec<(BridgeBundle, String)>> {
+        let mut rejected = Vec::new();
         for bundle in bundles {
             let hash = bundle.hash()?;
+            if let Err(e) = limits.validate_bundle(&bundle) {
+                rejected.push((bundle, e.to_string()));
+                continue;
+            }
+
+            if let Some(pooled) = self.conflict(&hash, &bundle) {
+                let reason = format!(
+                    "Spending the notes of bundle {}",
+                    bs58::encode(pooled).into_string()
+                );
+                rejected.push((bundle, reason));
+                continue;
+            }
+
             self.in_progress.insert(hash, bundle);
         }
-        Ok(())
+        Ok(rejected)
+    }
+
+    /// Get the pooled bundle spending the same notes as the bundle
+    fn conflict(&self, hash: &Hash, bundle: &BridgeBundle) -> Option<Hash> {
+        self.in_progress
+            .iter()
+            .chain(&self.completed)
+            .find(|(pooled, pending)| *pooled != hash && pending.conflicts(bundle))
+            .map(|(pooled, _)| *pooled)
     }
 
     /// Complete a bridge bundle
@@ -64,10 +127,175 @@ impl BridgePool {
         self.in_progress.remove(&bundle_hash);
     }
 
-    /// Pack the completed bridge requests
-    pub fn pack(&mut self) -> BTreeMap<Hash, BridgeBundle> {
-        let packed = self.completed.clone();
-        self.completed.clear();
-        packed
+    /// Pack the completed bridge requests within the limits
+    ///
+    /// Returns the packed bundles with their encoded size, the
+    /// overflow stays in the pool. The bundles paying the bridge requests
+    /// already included are dropped, and the ones sharing the bridge
+    /// requests with the packed bundles wait for the next blocks.
+    pub fn pack(
+        &mut self,
+        limits: &Limits,
+        max_bytes: usize,
+        exists: impl Fn(&[u8]) -> Result<bool>,
+    ) -> Result<(BTreeMap<Hash, BridgeBundle>, usize)> {
+        let mut packed = BTreeMap::new();
+        let mut dropped = Vec::new();
+        let mut included = BTreeSet::new();
+        let mut bytes = 0;
+        let mut bridges: BTreeMap<Chain, u32> = BTreeMap::new();
+        for (hash, bundle) in &self.completed {
+            if packed.len() >= limits.bundles as usize {
+                break;
+            }
+
+            let mut stale = false;
+            for bridge in &bundle.bridge {
+                stale |= exists(&bridge.txid)?;
+            }
+
+            if stale {
+                tracing::warn!(
+                    "Dropping bundle {} of the bridge requests already included",
+                    bs58::encode(hash).into_string()
+                );
+                dropped.push(*hash);
+                continue;
+            }
+
+            if bundle.bridge.iter().any(|b| included.contains(&b.txid)) {
+                continue;
+            }
+
+            let count = bridges.get(&bundle.target).copied().unwrap_or_default();
+            if count + bundle.bridge.len() as u32 > limits.bridges(bundle.target) {
+                continue;
+            }
+
+            let size = hash.len() + postcard::to_allocvec(bundle)?.len();
+            if bytes + size > max_bytes {
+                continue;
+            }
+
+            bytes += size;
+            bridges.insert(bundle.target, count + bundle.bridge.len() as u32);
+            included.extend(bundle.bridge.iter().map(|b| &b.txid));
+            packed.insert(*hash, bundle.clone());
+        }
+
+        for hash in packed.keys().chain(&dropped) {
+            self.completed.remove(hash);
+        }
+        Ok((packed, bytes))
+    }
+
+    /// Restore the packed bridge requests
+    pub fn restore(&mut self, bundles: BTreeMap<Hash, BridgeBundle>) {
+        self.completed.extend(bundles);
     }
 }
+
+#[test]
+fn test_reject_oversize() -> Result<()> {
+    use zcore::{ex::Bridge, registry::Coin};
+
+    let bridge = Bridge {
+        coin: Coin::Zec,
+        recipient: vec![1; 32],
+        amount: 100_000,
+        fee: 10_000,
+        source: Chain::Solana,
+        target: Chain::Zcash,
+        txid: vec![2; 64],
+    };
+    let bundle = BridgeBundle {
+        target: Chain::Zcash,
+        bridge: vec![bridge.clone(); 2],
+        data: Vec::new(),
+        signatures: Vec::new(),
+    };
+    let limits = Limits {
+        zcash: 1,
+        ..Default::default()
+    };
+
+    let mut pool = BridgePool::new(1);
+    let rejected = pool.dev_pack(vec![bundle.clone()], &limits)?;
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(pool.completed(), 0);
+
+    let limits = Limits {
+        bytes: 64,
+        ..Default::default()
+    };
+    let rejected = pool.queue(vec![bundle.clone()], &limits)?;
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(pool.in_progress(), 0);
+
+    let rejected = pool.dev_pack(vec![bundle], &Limits::default())?;
+    assert!(rejected.is_empty());
+    assert_eq!(pool.completed(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_reject_conflict() -> Result<()> {
+    let mut bundle = BridgeBundle::new(Chain::Zcash);
+    bundle.data = [[1; 32], [2; 32]].concat();
+    let mut conflict = BridgeBundle::new(Chain::Zcash);
+    conflict.data = [[2; 32], [3; 32]].concat();
+    let mut other = BridgeBundle::new(Chain::Zcash);
+    other.data = [[4; 32], [5; 32]].concat();
+
+    let mut pool = BridgePool::new(1);
+    let rejected = pool.queue(vec![bundle.clone()], &Limits::default())?;
+    assert!(rejected.is_empty());
+
+    let rejected = pool.dev_pack(vec![conflict, other], &Limits::default())?;
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].0.data, [[2; 32], [3; 32]].concat());
+    assert_eq!(pool.completed(), 1);
+
+    // the bundle does not conflict with itself
+    let rejected = pool.queue(vec![bundle], &Limits::default())?;
+    assert!(rejected.is_empty());
+    assert_eq!(pool.in_progress(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_pack_duplicates() -> Result<()> {
+    use zcore::{ex::Bridge, registry::Coin};
+
+    let bundle = |txids: &[u8], nullifier: u8| BridgeBundle {
+        target: Chain::Zcash,
+        bridge: txids
+            .iter()
+            .map(|txid| Bridge {
+                coin: Coin::Zec,
+                recipient: vec![1; 32],
+                amount: 100_000,
+                fee: 10_000,
+                source: Chain::Solana,
+                target: Chain::Zcash,
+                txid: vec![*txid; 64],
+            })
+            .collect(),
+        data: vec![nullifier; 32],
+        signatures: Vec::new(),
+    };
+
+    let mut pool = BridgePool::new(1);
+    let bundles = vec![bundle(&[1], 1), bundle(&[2, 3], 2), bundle(&[3], 3)];
+    assert!(pool.dev_pack(bundles, &Limits::default())?.is_empty());
+
+    // the included bundle is dropped, the duplicated one waits
+    let (packed, _) = pool.pack(&Limits::default(), usize::MAX, |txid| Ok(txid[0] == 1))?;
+    assert_eq!(packed.len(), 1);
+    assert_eq!(pool.completed(), 1);
+
+    let (packed, _) = pool.pack(&Limits::default(), usize::MAX, |_| Ok(false))?;
+    assert_eq!(packed.len(), 1);
+    assert_eq!(pool.completed(), 0);
+    Ok(())
+}
@@ -2,7 +2,12 @@
 
 use anyhow::Result;
 use bridge::BridgePool;
-use zcore::{ex::Receipt, Extrinsic};
+use std::collections::BTreeSet;
+use zcore::{
+    ex::Receipt,
+    state::{Limits, EXTRINSIC_OVERHEAD},
+    Extrinsic,
+};
 
 mod bridge;
 
@@ -25,11 +30,52 @@ impl Pool {
         }
     }
 
-    /// Pack the pool into an extrinsic
-    pub fn pack(&mut self) -> Result<Extrinsic> {
-        let receipts = self.receipt.drain(..).collect();
-        let bridge = self.bridge.pack();
+    /// Pack the pool into an extrinsic within the limits
+    ///
+    /// The overflow stays in the pool for the next blocks. The bridge
+    /// requests and the receipts already included never reach the
+    /// extrinsic, see [`BridgePool::pack`], the duplicated receipts of an
+    /// anchor are dropped.
+    pub fn pack(
+        &mut self,
+        limits: &Limits,
+        exists: impl Fn(&[u8]) -> Result<bool>,
+        receipted: impl Fn(&[u8]) -> Result<bool>,
+    ) -> Result<Extrinsic> {
+        let max_bytes = (limits.bytes as usize).saturating_sub(EXTRINSIC_OVERHEAD);
+        let (bridge, mut bytes) = self.bridge.pack(limits, max_bytes, exists)?;
+
+        let mut receipts = Vec::new();
+        let mut pending = Vec::new();
+        let mut anchors = BTreeSet::new();
+        for receipt in &self.receipt {
+            if anchors.contains(&receipt.anchor) || receipted(&receipt.anchor)? {
+                tracing::warn!(
+                    "Dropping duplicate receipt of anchor {}",
+                    bs58::encode(&receipt.anchor).into_string()
+                );
+                continue;
+            }
+
+            anchors.insert(&receipt.anchor);
+            let size = postcard::to_allocvec(receipt)?.len();
+            if receipts.len() >= limits.receipts as usize || bytes + size > max_bytes {
+                pending.push(receipt.clone());
+                continue;
+            }
+
+            bytes += size;
+            receipts.push(receipt.clone());
+        }
+
+        self.receipt = pending;
         let extrinsic = Extrinsic { bridge, receipts };
         Ok(extrinsic)
     }
+
+    /// Restore a packed extrinsic back into the pool
+    pub fn restore(&mut self, extrinsic: Extrinsic) {
+        self.bridge.restore(extrinsic.bridge);
+        self.receipt.extend(extrinsic.receipts);
+    }
 }
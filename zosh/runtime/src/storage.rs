@@ -2,7 +2,11 @@
 
 use anyhow::Result;
 use std::sync::Arc;
-use zcore::{state::key, Block, State, TrieKey};
+use zcore::{
+    ex::{Bridge, Receipt},
+    state::key,
+    Block, Hash, State, TrieKey,
+};
 
 /// The storage for the zosh bridge
 pub trait Storage: Clone + Send + Sync + 'static {
@@ -23,6 +27,18 @@ pub trait Storage: Clone + Send + Sync + 'static {
             state.present = postcard::from_bytes(&value)?;
         }
 
+        if let Some(value) = self.get(&key::FEE_KEY)? {
+            state.fee = postcard::from_bytes(&value)?;
+        }
+
+        if let Some(value) = self.get(&key::COLLECTED_KEY)? {
+            state.collected = postcard::from_bytes(&value)?;
+        }
+
+        if let Some(value) = self.get(&key::LIMITS_KEY)? {
+            state.limits = postcard::from_bytes(&value)?;
+        }
+
         Ok(state)
     }
 
@@ -32,17 +48,38 @@ pub trait Storage: Clone + Send + Sync + 'static {
     /// Batch the operations to the storage
     fn commit(&self, commit: Commit) -> Result<()>;
 
-    /// Set the block to the storage
+    /// Set the block to the storage, indexed by the hash and the slot
     fn set_block(&self, block: &Block) -> Result<()>;
 
-    /// Set the transactions to the storage
+    /// Get the block by its hash
+    fn block(&self, hash: &Hash) -> Result<Option<Block>>;
+
+    /// Get the block hash of the slot
+    fn block_hash(&self, slot: u32) -> Result<Option<Hash>>;
+
+    /// Set the transactions to the storage, indexed to the slot of their block
     ///
     /// TODO: use reference instead of cloning
-    fn set_txs(&self, txs: Vec<Vec<u8>>) -> Result<()>;
+    fn set_txs(&self, slot: u32, txs: Vec<Vec<u8>>) -> Result<()>;
+
+    /// Get the slot of the block including the transaction
+    fn tx_slot(&self, txid: &[u8]) -> Result<Option<u32>>;
 
     /// Check if transaction id exists in the storage
     fn exists(&self, key: &[u8]) -> Result<bool>;
 
+    /// Set the bridge requests to the storage, indexed by the source txid
+    fn set_bridges(&self, bridges: Vec<Bridge>) -> Result<()>;
+
+    /// Get the bridge request by the source txid
+    fn bridge(&self, txid: &[u8]) -> Result<Option<Bridge>>;
+
+    /// Set the receipts to the storage, indexed by the anchor
+    fn set_receipts(&self, receipts: Vec<Receipt>) -> Result<()>;
+
+    /// Get the receipt by the source txid of its bridge request
+    fn receipt(&self, anchor: &[u8]) -> Result<Option<Receipt>>;
+
     /// Get the root of the state
     fn root(&self) -> Result<[u8; 32]>;
 }
@@ -64,14 +101,42 @@ impl<S: Storage> Storage for Arc<S> {
         self.as_ref().set_block(block)
     }
 
-    fn set_txs(&self, txs: Vec<Vec<u8>>) -> Result<()> {
-        self.as_ref().set_txs(txs)
+    fn block(&self, hash: &Hash) -> Result<Option<Block>> {
+        self.as_ref().block(hash)
+    }
+
+    fn block_hash(&self, slot: u32) -> Result<Option<Hash>> {
+        self.as_ref().block_hash(slot)
+    }
+
+    fn set_txs(&self, slot: u32, txs: Vec<Vec<u8>>) -> Result<()> {
+        self.as_ref().set_txs(slot, txs)
+    }
+
+    fn tx_slot(&self, txid: &[u8]) -> Result<Option<u32>> {
+        self.as_ref().tx_slot(txid)
     }
 
     fn exists(&self, key: &[u8]) -> Result<bool> {
         self.as_ref().exists(key)
     }
 
+    fn set_bridges(&self, bridges: Vec<Bridge>) -> Result<()> {
+        self.as_ref().set_bridges(bridges)
+    }
+
+    fn bridge(&self, txid: &[u8]) -> Result<Option<Bridge>> {
+        self.as_ref().bridge(txid)
+    }
+
+    fn set_receipts(&self, receipts: Vec<Receipt>) -> Result<()> {
+        self.as_ref().set_receipts(receipts)
+    }
+
+    fn receipt(&self, anchor: &[u8]) -> Result<Option<Receipt>> {
+        self.as_ref().receipt(anchor)
+    }
+
     fn root(&self) -> Result<[u8; 32]> {
         self.as_ref().root()
     }
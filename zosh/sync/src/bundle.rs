@@ -1,17 +1,27 @@
 //! Validation interfaces for bridge bundles
 
-use crate::{zcash, ChainFormatEncoder, Sync};
+use crate::{zcash::memo, ChainFormatEncoder, Expiry, Sync, TxStatus, Unconfirmed};
 use anyhow::Result;
-use std::{collections::HashSet, mem};
+use std::{
+    collections::{HashSet, VecDeque},
+    mem,
+    sync::atomic::Ordering,
+    time::Instant,
+};
 use zcore::{
-    ex::{Bridge, BridgeBundle, Receipt},
+    ex::{Bridge, BridgeBundle},
     registry::{Chain, Coin},
+    state::Limits,
 };
 
 impl Sync {
     /// Bundle the bridge requests
     ///
-    /// We need to sign the bundles after processed.
+    /// We need to sign the bundles after processed, the sent transactions
+    /// are tracked until they reach the confirmation depth, see [`Sync::confirm`].
+    ///
+    /// The bundles are checked against the block limits before signing,
+    /// the sent transactions must be able to reach the chain.
     ///
     /// TODO: make the bundling process in parallel.
     ///
@@ -20,12 +30,42 @@ impl Sync {
     pub async fn bundle(
         &mut self,
         bridges: Vec<Bridge>,
-    ) -> Result<(Vec<BridgeBundle>, Vec<Receipt>)> {
+        limits: &Limits,
+    ) -> Result<Vec<BridgeBundle>> {
         let mut bridges: HashSet<Bridge> = bridges.into_iter().collect();
         bridges.extend(mem::take(&mut self.unresolved));
         let mut sol_bundles = Vec::new();
         let mut zcash_bundles = Vec::new();
         for bridge in bridges {
+            let reorged = match bridge.source {
+                Chain::Zcash => bridge
+                    .txid
+                    .zcash_txid()
+                    .and_then(|txid| self.zcash.deposits.reorged(&txid)),
+                Chain::Solana => Ok(false),
+            };
+
+            // the bridge requests failed to check wait for the next round
+            match reorged {
+                Ok(false) => {}
+                Ok(true) => {
+                    tracing::warn!(
+                        "Dropping bridge request of reorged deposit {}",
+                        bs58::encode(&bridge.txid).into_string()
+                    );
+                    self.reorged.push(bridge);
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to check the deposit of bridge request {}: {e:?}",
+                        bs58::encode(&bridge.txid).into_string()
+                    );
+                    self.unresolved.push(bridge);
+                    continue;
+                }
+            }
+
             match bridge.target {
                 Chain::Solana => sol_bundles.push(bridge),
                 Chain::Zcash => zcash_bundles.push(bridge),
@@ -33,42 +73,90 @@ impl Sync {
         }
 
         let mut bundles = Vec::new();
-        let mut receipts = Vec::new();
         if !sol_bundles.is_empty() {
-            let (sol_bundles, sol_receipts) = self.bundle_sol_bridges(sol_bundles).await?;
-            bundles.extend(sol_bundles);
-            receipts.extend(sol_receipts);
+            bundles.extend(self.bundle_sol_bridges(sol_bundles, limits).await?);
         }
 
         if !zcash_bundles.is_empty() {
-            let (zcash_bundles, zcash_receipts) = self.bundle_zcash_bridges(zcash_bundles).await?;
-            bundles.extend(zcash_bundles);
-            receipts.extend(zcash_receipts);
+            bundles.extend(self.bundle_zcash_bridges(zcash_bundles, limits).await?);
+        }
+
+        self.metrics
+            .unresolved
+            .store(self.unresolved.len(), Ordering::Relaxed);
+        Ok(bundles)
+    }
+
+    /// Rebuild the expired zcash transactions
+    ///
+    /// The expired transactions can never be mined, so their bridge requests
+    /// are bundled again with fresh notes and anchors then re-signed. The
+    /// receipts are still generated only after the new transactions are
+    /// mined, see [`Sync::confirm`].
+    ///
+    /// On failure, the expired bridge requests not sent, requeued or
+    /// flagged yet stay queued for the next round.
+    pub async fn rebuild(&mut self, limits: &Limits) -> Result<Vec<BridgeBundle>> {
+        let expired = mem::take(&mut self.expired);
+        if expired.is_empty() {
+            return Ok(Vec::new());
         }
 
-        Ok((bundles, receipts))
+        tracing::info!("Rebuilding {} expired zcash bridge requests", expired.len());
+        let bundles = match self.bundle_zcash_bridges(expired.clone(), limits).await {
+            Ok(bundles) => bundles,
+            Err(e) => {
+                let handled = self
+                    .unconfirmed
+                    .iter()
+                    .flat_map(|unconfirmed| &unconfirmed.bridges)
+                    .chain(&self.unresolved)
+                    .chain(self.flagged.iter().map(|(bridge, _)| bridge))
+                    .collect::<HashSet<_>>();
+                let queued = expired
+                    .into_iter()
+                    .filter(|bridge| !handled.contains(bridge))
+                    .collect::<Vec<_>>();
+                self.expired.extend(queued);
+                return Err(e);
+            }
+        };
+        self.metrics
+            .unresolved
+            .store(self.unresolved.len(), Ordering::Relaxed);
+        Ok(bundles)
     }
 
     /// Bundle the bridge requests for solana
     ///
     /// TODO: make this in parallel
-    ///
-    /// FIXME: we should not have the receipts here, should be placed
-    /// in the network layer.
     pub async fn bundle_sol_bridges(
         &mut self,
         bridges: Vec<Bridge>,
-    ) -> Result<(Vec<BridgeBundle>, Vec<Receipt>)> {
+        limits: &Limits,
+    ) -> Result<Vec<BridgeBundle>> {
         let mut bundles = Vec::new();
-        let mut receipts = Vec::new();
-        for unbundled in bridges.chunks(Chain::Solana.max_bundle_size()) {
-            let Ok((bundle, transaction)) = self.solana.bundle(unbundled).await.inspect_err(|e| {
+        let Some(mut chunks) = self.chunks(Chain::Solana, bridges, limits) else {
+            return Ok(bundles);
+        };
+
+        while let Some(unbundled) = chunks.pop_front() {
+            let Ok((bundle, transaction)) = self.solana.bundle(&unbundled).await.inspect_err(|e| {
                 tracing::error!("Failed to bundle solana bridges: {:?}", e);
             }) else {
-                self.unresolved.extend(unbundled.to_vec());
+                self.metrics
+                    .bundle(Chain::Solana, |metrics| metrics.bundle_failures += 1);
+                self.unresolved.extend(unbundled);
                 continue;
             };
 
+            if let Err(e) = limits.validate_bundle(&bundle) {
+                self.split(&mut chunks, unbundled, e);
+                continue;
+            }
+
+            let blockhash = transaction.message.recent_blockhash;
+            let now = Instant::now();
             let Ok(signature) = self
                 .solana
                 .dev_sign_and_send(transaction, &self.dev_solana_mpc)
@@ -77,57 +165,103 @@ impl Sync {
                     tracing::error!("Failed to sign and send solana transaction: {:?}", e);
                 })
             else {
+                self.metrics
+                    .bundle(Chain::Solana, |metrics| metrics.send_failures += 1);
                 self.unresolved.extend(unbundled.to_vec());
                 continue;
             };
+            self.metrics.bundle(Chain::Solana, |metrics| {
+                metrics.latency.observe(now.elapsed());
+                metrics.sent += 1;
+            });
 
-            // sign the bundles
-            for bridge in unbundled {
-                receipts.push(Receipt {
-                    anchor: bridge.txid.clone(),
-                    coin: Coin::Zec,
-                    txid: signature.as_array().to_vec(),
-                    source: Chain::Zcash,
-                    target: Chain::Solana,
-                });
-
-                tracing::info!(
-                    "Fulfilled bridge request from Zcash({}) to Solana({})! amount={} recipient={}",
-                    bridge.txid.zcash_txid()?.to_string(),
-                    signature,
-                    bridge.amount,
-                    bridge.recipient.solana_address()?
-                );
-            }
-
+            // track the transaction until it reaches the confirmation depth
+            tracing::info!(
+                "Sent solana transaction {signature} for {} bridge requests",
+                unbundled.len()
+            );
+            self.unconfirmed.push(Unconfirmed {
+                txid: signature.as_array().to_vec(),
+                target: Chain::Solana,
+                expiry: Expiry::Blockhash(blockhash),
+                bridges: unbundled.to_vec(),
+                nullifiers: Vec::new(),
+            });
             bundles.push(bundle);
         }
 
-        Ok((bundles, receipts))
+        Ok(bundles)
     }
 
     /// Bundle the bridge requests for zcash
     ///
     /// TODO: prove then splitting data for passing the package on chain.
     ///
-    /// FIXME: handle the mempool case, we may not have enough available notes
-    /// to bundle when we got a lot of redeem requests at the same time, and
-    /// there could be double spending problem for the same note.
+    /// The notes spent by the sent transactions are reserved until they
+    /// expire, the bundles carry their nullifiers for the peers to detect
+    /// the conflicts.
     ///
-    /// We need to subscribing the notes we are using to other nodes in the network
-    /// layer as well, to deduplicating the transactions in bundles.
+    /// The bridge requests with undecodable recipients can never be paid,
+    /// they are flagged instead of blocking the valid ones in their chunks.
     pub async fn bundle_zcash_bridges(
         &mut self,
         bridges: Vec<Bridge>,
-    ) -> Result<(Vec<BridgeBundle>, Vec<Receipt>)> {
+        limits: &Limits,
+    ) -> Result<Vec<BridgeBundle>> {
+        let mut decodable = Vec::new();
+        for bridge in bridges {
+            match bridge.recipient.zcash_address(&self.zcash.network) {
+                Ok(_) => decodable.push(bridge),
+                Err(e) => {
+                    tracing::error!(
+                        "Failing bridge request {} with invalid recipient: {e}",
+                        bs58::encode(&bridge.txid).into_string()
+                    );
+                    self.flagged.push((bridge, e.to_string()));
+                }
+            }
+        }
+
         let mut bundles = Vec::new();
-        let mut receipts = Vec::new();
-        for unbundled in bridges.chunks(Chain::Zcash.max_bundle_size()) {
-            let Ok((bundle, utx)) = self.zcash.bundle(unbundled).await else {
+        let Some(mut chunks) = self.chunks(Chain::Zcash, decodable, limits) else {
+            return Ok(bundles);
+        };
+
+        while let Some(unbundled) = chunks.pop_front() {
+            let Ok((bundle, utx)) = self.zcash.bundle(&unbundled).await else {
+                self.metrics
+                    .bundle(Chain::Zcash, |metrics| metrics.bundle_failures += 1);
+                self.unresolved.extend(unbundled);
+                continue;
+            };
+
+            // the bridges left out of the bundle wait for the next round
+            let (unbundled, left) = unbundled.split_at(bundle.bridge.len());
+            self.unresolved.extend(left.to_vec());
+
+            if let Err(e) = limits.validate_bundle(&bundle) {
+                if let Err(e) = self.zcash.release(&bundle) {
+                    tracing::error!("Failed to release the reserved zcash notes: {e:?}");
+                }
+                self.split(&mut chunks, unbundled.to_vec(), e);
+                continue;
+            }
+
+            // the spent notes are checked before rebuilding the transaction
+            let Ok(nullifiers) = self.zcash.spends(&bundle).inspect_err(|e| {
+                tracing::error!("Failed to get the notes spent by zcash bundle: {:?}", e);
+            }) else {
+                self.metrics
+                    .bundle(Chain::Zcash, |metrics| metrics.bundle_failures += 1);
+                if let Err(e) = self.zcash.release(&bundle) {
+                    tracing::error!("Failed to release the reserved zcash notes: {e:?}");
+                }
                 self.unresolved.extend(unbundled.to_vec());
                 continue;
             };
 
+            let expiry = u32::from(utx.expiry_height());
+            let now = Instant::now();
             let Ok(txid) = self
                 .zcash
                 .dev_sign_and_send(utx, &self.dev_zcash_mpc)
@@ -136,41 +270,169 @@ impl Sync {
                     tracing::error!("Failed to sign and send zcash transaction: {:?}", e);
                 })
             else {
+                self.metrics
+                    .bundle(Chain::Zcash, |metrics| metrics.send_failures += 1);
+                if let Err(e) = self.zcash.release(&bundle) {
+                    tracing::error!("Failed to release the reserved zcash notes: {e:?}");
+                }
                 self.unresolved.extend(unbundled.to_vec());
                 continue;
             };
+            self.metrics.bundle(Chain::Zcash, |metrics| {
+                metrics.latency.observe(now.elapsed());
+                metrics.sent += 1;
+            });
 
-            // sign the bundles
-            for bridge in unbundled {
-                receipts.push(Receipt {
-                    anchor: bridge.txid.clone(),
-                    coin: Coin::Zec,
-                    txid: txid.as_ref().to_vec(),
-                    source: Chain::Solana,
-                    target: Chain::Zcash,
-                });
-
-                tracing::info!(
-                    "Fulfilled bridge request from Solana({}) to Zcash({})! amount={} recipient={}",
-                    bridge.txid.solana_signature()?,
-                    txid,
-                    bridge.amount,
-                    bridge
-                        .recipient
-                        .zcash_address(&self.zcash.network)?
-                        .encode(&zcash::Network::TestNetwork),
-                );
-            }
+            // track the transaction until it reaches the confirmation depth
+            tracing::info!(
+                "Sent zcash transaction {txid} for {} bridge requests",
+                unbundled.len()
+            );
+            self.unconfirmed.push(Unconfirmed {
+                txid: txid.as_ref().to_vec(),
+                target: Chain::Zcash,
+                expiry: Expiry::Height(expiry),
+                bridges: unbundled.to_vec(),
+                nullifiers,
+            });
             bundles.push(bundle);
         }
 
-        Ok((bundles, receipts))
+        Ok(bundles)
     }
 
-    /// Validate the bridge request
+    /// Chunk the bridge requests within the bundle size and the block limits
     ///
-    /// depends on the transaction id of the source chain.
-    pub async fn validate_bridge(&mut self, _bridge: &Bridge) -> Result<()> {
+    /// The bridge requests wait for the next round if the blocks take no
+    /// bridges targeting the chain.
+    fn chunks(
+        &mut self,
+        target: Chain,
+        bridges: Vec<Bridge>,
+        limits: &Limits,
+    ) -> Option<VecDeque<Vec<Bridge>>> {
+        let size = target
+            .max_bundle_size()
+            .min(limits.bridges(target) as usize);
+        if size == 0 {
+            tracing::warn!("No bridges targeting {target:?} are allowed in the blocks");
+            self.unresolved.extend(bridges);
+            return None;
+        }
+
+        Some(bridges.chunks(size).map(<[Bridge]>::to_vec).collect())
+    }
+
+    /// Split the bridge requests of an oversize bundle into the next chunks
+    ///
+    /// A single bridge request exceeding the limits can never be packed,
+    /// it is flagged.
+    fn split(
+        &mut self,
+        chunks: &mut VecDeque<Vec<Bridge>>,
+        mut bridges: Vec<Bridge>,
+        e: anyhow::Error,
+    ) {
+        if bridges.len() > 1 {
+            tracing::warn!("Splitting bundle of {} bridges: {e}", bridges.len());
+            let right = bridges.split_off(bridges.len() / 2);
+            chunks.push_front(right);
+            chunks.push_front(bridges);
+            return;
+        }
+
+        for bridge in bridges {
+            tracing::error!(
+                "Failing bridge request {} exceeding the block limits: {e}",
+                bs58::encode(&bridge.txid).into_string()
+            );
+            self.flagged.push((bridge, e.to_string()));
+        }
+    }
+
+    /// Validate the bridge request against the source chain
+    pub async fn validate_bridge(&mut self, bridge: &Bridge) -> Result<()> {
+        match bridge.source {
+            Chain::Zcash => self.validate_deposit(bridge).await,
+            Chain::Solana => self.validate_burn(bridge).await,
+        }
+    }
+
+    /// Validate the solana burn with the finalized transaction
+    ///
+    /// Checks that a top-level `burn` instruction of the zosh program is
+    /// executed with the amount and the zcash recipient of the request,
+    /// and the recipient decodes as a zcash address of the network, the
+    /// program only checks its prefix and length.
+    async fn validate_burn(&mut self, bridge: &Bridge) -> Result<()> {
+        let signature = bridge.txid.solana_signature()?;
+        if bridge.coin != Coin::Zec || bridge.target != Chain::Zcash {
+            anyhow::bail!(
+                "Unsupported burn {signature}: {:?} to {:?}",
+                bridge.coin,
+                bridge.target
+            );
+        }
+
+        let recipient = String::from_utf8(bridge.recipient.clone())?;
+        if let Err(e) = bridge.recipient.zcash_address(&self.zcash.network) {
+            anyhow::bail!("Invalid zcash recipient of burn {signature}: {e}");
+        }
+
+        let burned = self
+            .solana
+            .burns(&signature)
+            .await?
+            .into_iter()
+            .any(|(amount, to)| amount == bridge.amount && to == recipient);
+        if !burned {
+            anyhow::bail!(
+                "Transaction {signature} does not burn {} for recipient {recipient}",
+                bridge.amount
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Validate the zcash deposit with the wallet of the bridge
+    ///
+    /// Checks that the deposit is mined at the confirmation depth, and one of
+    /// its notes received by the bridge pays the amount with a memo to the
+    /// recipient.
+    async fn validate_deposit(&mut self, bridge: &Bridge) -> Result<()> {
+        let txid = bridge.txid.zcash_txid()?;
+        if bridge.coin != Coin::Zec || bridge.target != Chain::Solana {
+            anyhow::bail!(
+                "Unsupported deposit {txid}: {:?} to {:?}",
+                bridge.coin,
+                bridge.target
+            );
+        }
+
+        let required = self.depth.deposit;
+        match self.zcash.status(txid).await? {
+            TxStatus::Confirmed(depth) if depth >= required => {}
+            status => anyhow::bail!("Deposit {txid} is not mined at depth {required}: {status:?}"),
+        }
+
+        let paid = self
+            .zcash
+            .received(txid)
+            .await?
+            .into_iter()
+            .any(|(value, memo)| {
+                value == bridge.amount
+                    && memo::recipient(&memo).is_ok_and(|recipient| recipient == bridge.recipient)
+            });
+        if !paid {
+            anyhow::bail!(
+                "Deposit {txid} does not pay {} to the bridge for recipient {}",
+                bridge.amount,
+                bs58::encode(&bridge.recipient).into_string()
+            );
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,35 @@
+//! Confirmation depth configuration
+
+use serde::{Deserialize, Serialize};
+
+/// Confirmation depth configuration
+///
+/// The receipts of the bridge requests are generated only after the
+/// transactions on the target chain reach these depths.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Depth {
+    /// The required confirmations on solana
+    pub solana: u64,
+
+    /// The required confirmations on zcash
+    pub zcash: u64,
+
+    /// The required confirmations of the zcash deposits before bridging
+    #[serde(default = "default_deposit")]
+    pub deposit: u64,
+}
+
+impl Default for Depth {
+    fn default() -> Self {
+        Self {
+            solana: 32,
+            zcash: 3,
+            deposit: default_deposit(),
+        }
+    }
+}
+
+/// The default confirmations of the zcash deposits
+fn default_deposit() -> u64 {
+    10
+}
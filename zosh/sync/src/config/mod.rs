@@ -8,8 +8,9 @@ use std::{
     path::{Path, PathBuf},
     sync::LazyLock,
 };
-pub use {crate::zcash, key::Key, network::Network, rpc::Rpc};
+pub use {crate::zcash, depth::Depth, key::Key, network::Network, rpc::Rpc};
 
+mod depth;
 mod key;
 mod network;
 mod rpc;
@@ -48,6 +49,10 @@ pub struct Config {
 
     /// Network
     pub network: Network,
+
+    /// Confirmation depths of the target chains
+    #[serde(default)]
+    pub depth: Depth,
 }
 
 impl Config {
@@ -79,6 +84,7 @@ impl Config {
             lightwalletd: self.rpc.lightwalletd.clone(),
             network: self.network.clone().into(),
             ufvk,
+            depth: self.depth.deposit,
         })
     }
 
@@ -97,6 +103,7 @@ impl Config {
                     .into_string(),
             },
             network: Network::Testnet,
+            depth: Depth::default(),
         };
         fs::write(target, toml::to_string_pretty(&config)?)?;
         Ok(config)
@@ -2,7 +2,7 @@
 
 use crate::{
     solana::{Pubkey, Signature},
-    zcash::{AddressCodec, Network, TxId, UnifiedAddress},
+    zcash::{Network, Recipient, TxId},
 };
 use anyhow::{Context, Result};
 use zcore::FixedBytes;
@@ -12,8 +12,8 @@ pub trait ChainFormatEncoder {
     /// Encode the solana address to the chain format
     fn solana_address(&self) -> Result<Pubkey>;
 
-    /// Encode the zcash address to the chain format
-    fn zcash_address(&self, network: &Network) -> Result<UnifiedAddress>;
+    /// Encode the zcash address to the receiver it pays
+    fn zcash_address(&self, network: &Network) -> Result<Recipient>;
 
     /// Encode the solana signature to the chain format
     fn solana_signature(&self) -> Result<Signature>;
@@ -29,9 +29,8 @@ impl<T: AsRef<[u8]>> ChainFormatEncoder for T {
         ))
     }
 
-    fn zcash_address(&self, network: &Network) -> Result<UnifiedAddress> {
-        UnifiedAddress::decode(network, String::from_utf8(self.as_ref().to_vec())?.as_str())
-            .map_err(|e| anyhow::anyhow!("Invalid zcash address: {e:?}"))
+    fn zcash_address(&self, network: &Network) -> Result<Recipient> {
+        Recipient::decode(network, String::from_utf8(self.as_ref().to_vec())?.as_str())
     }
 
     fn solana_signature(&self) -> Result<Signature> {
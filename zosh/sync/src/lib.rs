@@ -2,16 +2,29 @@
 
 use crate::solana::SolanaClient;
 use anyhow::Result;
-use tokio::sync::mpsc;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::{mpsc, Mutex};
 use zcore::ex::Bridge;
-pub use {config::Config, encoder::ChainFormatEncoder, solana::ZoshClient, zcash::ZcashClient};
+pub use {
+    config::Config,
+    encoder::ChainFormatEncoder,
+    metrics::Metrics,
+    receipt::{Expiry, InvalidReceipt, TxStatus, Unconfirmed},
+    solana::ZoshClient,
+    zcash::ZcashClient,
+};
 
 mod bundle;
 pub mod config;
 mod encoder;
+pub mod metrics;
+mod receipt;
 pub mod solana;
 pub mod zcash;
 
+/// The interval of polling the zcash deposits
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
 /// The sync data source
 pub struct Sync {
     /// The development MPC
@@ -28,6 +41,25 @@ pub struct Sync {
 
     /// unresolved bundles
     pub unresolved: Vec<Bridge>,
+
+    /// The sent transactions waiting for the confirmation depth
+    pub unconfirmed: Vec<Unconfirmed>,
+
+    /// The bridge requests dropped as their source deposits are reorged
+    pub reorged: Vec<Bridge>,
+
+    /// The bridge requests failed terminally, with the reasons, e.g. the
+    /// invalid receipts or the undecodable recipients
+    pub flagged: Vec<(Bridge, String)>,
+
+    /// The bridge requests of the expired zcash transactions to rebuild
+    pub expired: Vec<Bridge>,
+
+    /// The confirmation depths of the target chains
+    pub depth: config::Depth,
+
+    /// The metrics shared with the clients
+    pub metrics: Arc<Metrics>,
 }
 
 impl Sync {
@@ -46,25 +78,68 @@ impl Sync {
             postcard::from_bytes(&bs58::decode(&config.key.zcash).into_vec()?)?;
         let dev_solana_mpc: solana::GroupSigners =
             postcard::from_bytes(&bs58::decode(&config.key.solana).into_vec()?)?;
-        Ok(Self {
+        let sync = Self {
             dev_solana_mpc,
             dev_zcash_mpc,
             zcash,
             solana,
             unresolved: Default::default(),
-        })
+            unconfirmed: Default::default(),
+            reorged: Default::default(),
+            flagged: Default::default(),
+            expired: Default::default(),
+            depth: config.depth.clone(),
+            metrics: Default::default(),
+        };
+
+        let metrics = sync.metrics.clone();
+        Ok(sync.with_metrics(metrics))
+    }
+
+    /// Share the metrics with the clients, and other sync instances
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.zcash.metrics = metrics.clone();
+        self.solana.metrics = metrics.clone();
+        self.metrics = metrics;
+        self
     }
 
     /// Spawn the sync service
-    pub fn spawn(self, tx: mpsc::Sender<Bridge>) {
-        tokio::spawn(async move { self.start(tx).await });
+    ///
+    /// The zcash deposits are polled with the instance shared with the
+    /// bundler and the author, so the wallet and its reservations are only
+    /// opened once. The solana logs are subscribed with a client of their
+    /// own, which holds no local state.
+    pub async fn spawn(sync: Arc<Mutex<Self>>, tx: mpsc::Sender<Bridge>) -> Result<()> {
+        let mut solana = SolanaClient::new(&Config::load()?).await?;
+        solana.metrics = sync.lock().await.metrics.clone();
+        let deposits = tx.clone();
+        tokio::spawn(async move { solana.subscribe(tx).await });
+        tokio::spawn(async move { Self::poll(sync, deposits).await });
+        Ok(())
     }
 
-    /// Start the sync
-    pub async fn start(mut self, tx: mpsc::Sender<Bridge>) {
-        tokio::select! {
-            r = self.zcash.subscribe(tx.clone()) => r,
-            r = self.solana.subscribe(tx.clone()) => r
+    /// Poll the zcash deposits with the shared instance
+    ///
+    /// The lock is released before emitting the deposits, the relay locks
+    /// the instance to validate them.
+    async fn poll(sync: Arc<Mutex<Self>>, tx: mpsc::Sender<Bridge>) {
+        loop {
+            let polled = sync.lock().await.zcash.poll().await;
+            match polled {
+                Ok(bridges) => {
+                    for bridge in bridges {
+                        if tx.send(bridge).await.is_err() {
+                            tracing::error!("The relay is closed, stop polling zcash deposits");
+                            return;
+                        }
+                    }
+                }
+                Err(e) => tracing::error!("Zcash light client subscription error: {e:?}"),
+            }
+
+            // The block time of zcash is 75 secs, using 10 secs is fine here.
+            tokio::time::sleep(POLL_INTERVAL).await;
         }
     }
 }
@@ -0,0 +1,85 @@
+//! Metrics of the sync services
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, AtomicUsize},
+        Mutex,
+    },
+};
+use zcore::{metrics::Histogram, registry::Chain};
+
+/// The upper bounds of the bundle latency buckets in seconds
+pub const BUNDLE_BUCKETS: [f64; 9] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// The bundle metrics of a target chain
+#[derive(Debug, Clone)]
+pub struct BundleMetrics {
+    /// The latency of signing and sending the bundles
+    pub latency: Histogram,
+
+    /// The number of the sent bundles
+    pub sent: u64,
+
+    /// The number of bundles failed to build
+    pub bundle_failures: u64,
+
+    /// The number of bundles failed to sign or send
+    pub send_failures: u64,
+
+    /// The number of sent bundles expired before mined
+    pub expired: u64,
+}
+
+impl Default for BundleMetrics {
+    fn default() -> Self {
+        Self {
+            latency: Histogram::new(&BUNDLE_BUCKETS),
+            sent: 0,
+            bundle_failures: 0,
+            send_failures: 0,
+            expired: 0,
+        }
+    }
+}
+
+/// The metrics shared by the sync services
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// The height the zcash wallet is scanned to
+    pub zcash_height: AtomicU32,
+
+    /// The height of the zcash chain tip
+    pub zcash_tip: AtomicU32,
+
+    /// The number of the blacklisted zcash notes
+    pub blacklisted: AtomicUsize,
+
+    /// The number of the detected zcash reorgs
+    pub zcash_reorgs: AtomicU64,
+
+    /// The number of the unresolved bridge requests
+    pub unresolved: AtomicUsize,
+
+    /// The number of the solana subscription reconnects
+    pub solana_reconnects: AtomicU64,
+
+    /// The bundle metrics by the target chain
+    pub bundles: Mutex<BTreeMap<Chain, BundleMetrics>>,
+}
+
+impl Metrics {
+    /// Update the bundle metrics of the target chain
+    pub fn bundle(&self, target: Chain, update: impl FnOnce(&mut BundleMetrics)) {
+        let mut bundles = self.bundles.lock().unwrap_or_else(|e| e.into_inner());
+        update(bundles.entry(target).or_default());
+    }
+
+    /// Snapshot the bundle metrics of all target chains
+    pub fn bundles(&self) -> BTreeMap<Chain, BundleMetrics> {
+        self.bundles
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
@@ -0,0 +1,432 @@
+//! Receipt generation and verification against the target chains
+
+use crate::{ChainFormatEncoder, Sync};
+use anyhow::Result;
+use solana_sdk::hash::Hash;
+use std::{fmt, mem};
+use zcore::{
+    ex::{Bridge, BridgeBundle, Receipt},
+    registry::Chain,
+};
+
+/// The status of a transaction on the target chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    /// The transaction is not included in the target chain yet
+    Pending,
+
+    /// The transaction is included with the confirmation depth
+    Confirmed(u64),
+
+    /// The transaction failed on the target chain
+    Failed,
+
+    /// The transaction is found neither in the mempool nor in the chain
+    Missing,
+}
+
+/// The expiry of a sent transaction
+#[derive(Debug, Clone)]
+pub enum Expiry {
+    /// The recent blockhash of a solana transaction
+    Blockhash(Hash),
+
+    /// The expiry height of a zcash transaction
+    Height(u32),
+}
+
+/// The error of a receipt not matching the target chain
+///
+/// The other errors of the receipt verification are transient, e.g. the
+/// failures of the RPCs, the receipts are verified again later.
+#[derive(Debug)]
+pub struct InvalidReceipt(pub String);
+
+impl fmt::Display for InvalidReceipt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidReceipt {}
+
+/// A sent transaction waiting for the confirmation depth
+#[derive(Debug, Clone)]
+pub struct Unconfirmed {
+    /// The transaction id on the target chain
+    pub txid: Vec<u8>,
+
+    /// The target chain
+    pub target: Chain,
+
+    /// The expiry of the transaction
+    pub expiry: Expiry,
+
+    /// The bridge requests fulfilled by the transaction
+    pub bridges: Vec<Bridge>,
+
+    /// The nullifiers of the wallet notes spent by the transaction
+    pub nullifiers: Vec<[u8; 32]>,
+}
+
+impl Unconfirmed {
+    /// Get the receipts of the fulfilled bridge requests
+    pub fn receipts(&self) -> Vec<Receipt> {
+        self.bridges
+            .iter()
+            .map(|bridge| Receipt {
+                anchor: bridge.txid.clone(),
+                coin: bridge.coin,
+                amount: bridge.net(),
+                txid: self.txid.clone(),
+                source: bridge.source,
+                target: bridge.target,
+            })
+            .collect()
+    }
+}
+
+impl Sync {
+    /// Generate the receipts of the sent transactions reached the
+    /// confirmation depth
+    ///
+    /// The bridge requests of the failed or expired transactions are
+    /// moved back to the unresolved queue, except the ones of the expired
+    /// zcash transactions, which wait for [`Sync::rebuild`] once the notes
+    /// they spent are still unspent.
+    ///
+    /// The bridge requests with invalid receipts are flagged for the
+    /// operators, the ones failed to verify are checked again later.
+    pub async fn confirm(&mut self) -> Result<Vec<Receipt>> {
+        let mut receipts = Vec::new();
+        for sent in mem::take(&mut self.unconfirmed) {
+            let status = match self.status(&sent).await {
+                Ok(status) => status,
+                Err(e) => {
+                    tracing::warn!("Failed to get the status of sent transaction: {e:?}");
+                    self.unconfirmed.push(sent);
+                    continue;
+                }
+            };
+
+            match status {
+                TxStatus::Confirmed(depth) if depth >= self.required(sent.target) => {
+                    let mut confirmed = Vec::new();
+                    let mut retry = Vec::new();
+                    for (receipt, bridge) in sent.receipts().into_iter().zip(&sent.bridges) {
+                        match self.verify_receipt(&receipt, bridge).await {
+                            Ok(()) => {}
+                            Err(e) if e.is::<InvalidReceipt>() => {
+                                tracing::error!("Invalid receipt of confirmed transaction: {e}");
+                                self.flagged.push((bridge.clone(), e.to_string()));
+                                continue;
+                            }
+                            Err(e) => {
+                                tracing::warn!("Failed to verify receipt, retrying: {e:?}");
+                                retry.push(bridge.clone());
+                                continue;
+                            }
+                        }
+
+                        tracing::info!(
+                            "Fulfilled bridge request from {:?}({}) to {:?}({})! amount={} fee={}",
+                            bridge.source,
+                            display(bridge.source, &bridge.txid),
+                            bridge.target,
+                            display(bridge.target, &receipt.txid),
+                            receipt.amount,
+                            bridge.fee,
+                        );
+                        confirmed.push(receipt);
+                    }
+                    receipts.extend(confirmed);
+                    if !retry.is_empty() {
+                        self.unconfirmed.push(Unconfirmed {
+                            bridges: retry,
+                            ..sent
+                        });
+                    }
+                }
+                TxStatus::Failed if sent.target == Chain::Zcash => {
+                    // the spent notes mean the transaction may be mined
+                    // after all, it is never paid twice
+                    match self.zcash.unspent() {
+                        Ok(unspent) if sent.nullifiers.iter().all(|n| unspent.contains(n)) => {}
+                        Ok(_) => {
+                            tracing::error!(
+                                "The notes of expired zcash transaction {} are spent, keep tracking",
+                                display(sent.target, &sent.txid)
+                            );
+                            self.unconfirmed.push(sent);
+                            continue;
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to get the unspent zcash notes: {e:?}");
+                            self.unconfirmed.push(sent);
+                            continue;
+                        }
+                    }
+
+                    tracing::warn!(
+                        "Sent zcash transaction {} expired at height {:?}, rebuild {} bridge requests",
+                        display(sent.target, &sent.txid),
+                        sent.expiry,
+                        sent.bridges.len()
+                    );
+                    self.metrics
+                        .bundle(Chain::Zcash, |metrics| metrics.expired += 1);
+                    self.expired.extend(sent.bridges);
+                }
+                TxStatus::Failed => {
+                    tracing::warn!(
+                        "Sent transaction {} failed on {:?}, requeue {} bridge requests",
+                        display(sent.target, &sent.txid),
+                        sent.target,
+                        sent.bridges.len()
+                    );
+                    self.unresolved.extend(sent.bridges);
+                }
+                _ => self.unconfirmed.push(sent),
+            }
+        }
+
+        Ok(receipts)
+    }
+
+    /// Stop tracking the sent transaction of a bundle rejected by the pool
+    ///
+    /// The bridge requests of the rejected bundles never reach a block, so
+    /// no receipts are generated for them.
+    pub fn untrack(&mut self, bundle: &BridgeBundle) {
+        self.unconfirmed
+            .retain(|sent| sent.bridges != bundle.bridge);
+    }
+
+    /// Verify a receipt against the target chain
+    ///
+    /// Checks that the target transaction reaches the confirmation depth
+    /// and delivers the net amount of the anchored bridge request to its
+    /// recipient.
+    ///
+    /// The mismatches are returned as [`InvalidReceipt`], the other errors
+    /// are transient.
+    pub async fn verify_receipt(&mut self, receipt: &Receipt, bridge: &Bridge) -> Result<()> {
+        check_receipt(receipt, bridge)?;
+        let required = self.required(receipt.target);
+        let delivered = match receipt.target {
+            Chain::Solana => {
+                let signature = receipt.txid.solana_signature().map_err(invalid)?;
+                let recipient = bridge.recipient.solana_address().map_err(invalid)?;
+                let status = self.solana.status(&signature).await?;
+                ensure_depth(status, required, receipt)?;
+
+                self.solana
+                    .mints(&signature)
+                    .await?
+                    .into_iter()
+                    .any(|(to, amount)| to == recipient && amount == receipt.amount)
+            }
+            Chain::Zcash => {
+                let txid = receipt.txid.zcash_txid().map_err(invalid)?;
+                let recipient = bridge
+                    .recipient
+                    .zcash_address(&self.zcash.network)
+                    .map_err(invalid)?;
+                let status = self.zcash.status(txid).await?;
+                ensure_depth(status, required, receipt)?;
+
+                self.zcash
+                    .sent(txid)
+                    .await?
+                    .into_iter()
+                    .any(|(to, amount)| to == recipient && amount == receipt.amount)
+            }
+        };
+
+        if !delivered {
+            return Err(invalid(format!(
+                "Transaction {} does not deliver {} to the recipient of {}",
+                display(receipt.target, &receipt.txid),
+                receipt.amount,
+                display(receipt.source, &receipt.anchor)
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Get the status of a sent transaction, checking the expiry
+    ///
+    /// The expiry is checked before the status, so a transaction mined in
+    /// between is never taken as expired. A zcash transaction fails only
+    /// once it is missing beyond its expiry height by the confirmation
+    /// depth, in case of the reorgs.
+    async fn status(&mut self, sent: &Unconfirmed) -> Result<TxStatus> {
+        let (status, expired) = match sent.expiry {
+            Expiry::Blockhash(blockhash) => {
+                let expired = self.solana.expired(&blockhash).await?;
+                let status = self.solana.status(&sent.txid.solana_signature()?).await?;
+                (status, expired && status == TxStatus::Pending)
+            }
+            Expiry::Height(height) => {
+                let tip = self.zcash.tip().await?;
+                let status = self.zcash.status(sent.txid.zcash_txid()?).await?;
+                let expired = dropped(height, tip, self.depth.zcash);
+                (status, expired && status == TxStatus::Missing)
+            }
+        };
+
+        Ok(match status {
+            _ if expired => TxStatus::Failed,
+            TxStatus::Missing => TxStatus::Pending,
+            status => status,
+        })
+    }
+
+    /// Get the required confirmation depth of the target chain
+    fn required(&self, target: Chain) -> u64 {
+        match target {
+            Chain::Solana => self.depth.solana,
+            Chain::Zcash => self.depth.zcash,
+        }
+    }
+}
+
+/// Check if a missing zcash transaction can never be mined
+///
+/// The transactions are dropped once the tip passes their expiry height
+/// by the reorg margin.
+fn dropped(expiry: u32, tip: u64, margin: u64) -> bool {
+    tip > expiry as u64 + margin
+}
+
+/// Check that the receipt matches the anchored bridge request
+fn check_receipt(receipt: &Receipt, bridge: &Bridge) -> Result<()> {
+    if receipt.anchor != bridge.txid
+        || receipt.source != bridge.source
+        || receipt.target != bridge.target
+        || receipt.coin != bridge.coin
+    {
+        return Err(invalid(format!(
+            "Receipt {} does not match the anchored bridge request",
+            display(receipt.target, &receipt.txid)
+        )));
+    }
+
+    if receipt.amount != bridge.net() {
+        return Err(invalid(format!(
+            "Invalid receipt amount: anchor={}, expected={}, got={}",
+            display(receipt.source, &receipt.anchor),
+            bridge.net(),
+            receipt.amount
+        )));
+    }
+
+    Ok(())
+}
+
+/// Wrap an error of the receipt verification as [`InvalidReceipt`]
+fn invalid(e: impl fmt::Display) -> anyhow::Error {
+    InvalidReceipt(e.to_string()).into()
+}
+
+/// Ensure the transaction reaches the required confirmation depth
+///
+/// The failed transactions invalidate the receipts, the pending or the
+/// shallow ones are checked again later.
+fn ensure_depth(status: TxStatus, required: u64, receipt: &Receipt) -> Result<()> {
+    match status {
+        TxStatus::Confirmed(depth) if depth >= required => Ok(()),
+        TxStatus::Failed => Err(invalid(format!(
+            "Transaction {} failed on {:?}",
+            display(receipt.target, &receipt.txid),
+            receipt.target
+        ))),
+        status => anyhow::bail!(
+            "Transaction {} is not confirmed at depth {required}: {status:?}",
+            display(receipt.target, &receipt.txid)
+        ),
+    }
+}
+
+/// Display the transaction id in the format of its chain
+fn display(chain: Chain, txid: &[u8]) -> String {
+    match chain {
+        Chain::Solana => txid.solana_signature().map(|s| s.to_string()),
+        Chain::Zcash => txid.zcash_txid().map(|t| t.to_string()),
+    }
+    .unwrap_or_else(|_| bs58::encode(txid).into_string())
+}
+
+#[test]
+fn test_check_receipt() -> Result<()> {
+    use zcore::registry::Coin;
+
+    let bridge = Bridge {
+        coin: Coin::Zec,
+        recipient: vec![1; 32],
+        amount: 100_000,
+        fee: 10_000,
+        source: Chain::Zcash,
+        target: Chain::Solana,
+        txid: vec![2; 32],
+    };
+    let sent = Unconfirmed {
+        txid: vec![3; 64],
+        target: Chain::Solana,
+        expiry: Expiry::Height(0),
+        bridges: vec![bridge.clone()],
+        nullifiers: Vec::new(),
+    };
+
+    let mut receipt = sent.receipts().remove(0);
+    assert_eq!(receipt.amount, 90_000);
+    check_receipt(&receipt, &bridge)?;
+
+    receipt.amount = bridge.amount;
+    assert!(check_receipt(&receipt, &bridge)
+        .unwrap_err()
+        .is::<InvalidReceipt>());
+
+    receipt.amount = bridge.net();
+    receipt.anchor = vec![4; 32];
+    assert!(check_receipt(&receipt, &bridge)
+        .unwrap_err()
+        .is::<InvalidReceipt>());
+    Ok(())
+}
+
+#[test]
+fn test_ensure_depth() -> Result<()> {
+    use zcore::registry::Coin;
+
+    let receipt = Receipt {
+        anchor: vec![2; 32],
+        coin: Coin::Zec,
+        amount: 90_000,
+        txid: vec![3; 32],
+        source: Chain::Solana,
+        target: Chain::Zcash,
+    };
+
+    ensure_depth(TxStatus::Confirmed(3), 3, &receipt)?;
+    assert!(ensure_depth(TxStatus::Failed, 3, &receipt)
+        .unwrap_err()
+        .is::<InvalidReceipt>());
+
+    // the pending and shallow transactions are transient
+    for status in [TxStatus::Pending, TxStatus::Confirmed(2)] {
+        assert!(!ensure_depth(status, 3, &receipt)
+            .unwrap_err()
+            .is::<InvalidReceipt>());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_dropped() {
+    // the expired transactions may still be reorged back
+    assert!(!dropped(100, 100, 3));
+    assert!(!dropped(100, 103, 3));
+    assert!(dropped(100, 104, 3));
+}
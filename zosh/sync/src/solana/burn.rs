@@ -0,0 +1,98 @@
+//! Verification of the burn transactions
+
+use crate::solana::SolanaClient;
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_rpc_client_types::config::RpcTransactionConfig;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status_client_types::UiTransactionEncoding;
+use std::time::Duration;
+use zosh::{
+    client::{AnchorDeserialize, Discriminator},
+    instruction::Burn,
+    BurnEvent,
+};
+
+/// The retries of fetching a finalized transaction
+const FETCH_RETRIES: usize = 3;
+
+/// The delay between the retries
+const FETCH_DELAY: Duration = Duration::from_secs(2);
+
+impl SolanaClient {
+    /// Get the burns executed by a finalized transaction
+    ///
+    /// Only the top-level `burn` instructions of the zosh program count, the
+    /// program logs and the inner instructions can be produced by any other
+    /// program. Returns the amount and the zcash recipient of the burns
+    /// with a matching `BurnEvent`.
+    pub async fn burns(&self, signature: &Signature) -> Result<Vec<(u64, String)>> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        };
+
+        let rpc = self.tx.program.rpc();
+        let mut retries = 0;
+        let tx = loop {
+            match rpc.get_transaction_with_config(signature, config).await {
+                Ok(tx) => break tx,
+                Err(e) if retries < FETCH_RETRIES => {
+                    tracing::debug!("Transaction {signature} is not finalized yet: {e}");
+                    retries += 1;
+                    tokio::time::sleep(FETCH_DELAY).await;
+                }
+                Err(e) => anyhow::bail!("Transaction {signature} is not finalized: {e}"),
+            }
+        };
+
+        let meta = tx.transaction.meta.ok_or(anyhow::anyhow!(
+            "Missing metadata of transaction {signature}"
+        ))?;
+        if let Some(err) = meta.err {
+            anyhow::bail!("Transaction {signature} failed: {err:?}");
+        }
+
+        let transaction = tx
+            .transaction
+            .transaction
+            .decode()
+            .ok_or(anyhow::anyhow!("Failed to decode transaction {signature}"))?;
+        let keys = transaction.message.static_account_keys();
+        let mut burns = Vec::new();
+        for instruction in transaction.message.instructions() {
+            if keys.get(instruction.program_id_index as usize) != Some(&zosh::ID) {
+                continue;
+            }
+
+            let Some(mut args) = instruction.data.strip_prefix(Burn::DISCRIMINATOR) else {
+                continue;
+            };
+
+            let burn = Burn::deserialize(&mut args)?;
+            burns.push((burn.amount, burn.zec_recipient));
+        }
+
+        let logs: Option<Vec<String>> = meta.log_messages.into();
+        let events = logs
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|log| burn_event(log))
+            .collect::<Vec<_>>();
+        burns.retain(|(amount, recipient)| {
+            events
+                .iter()
+                .any(|event| event.amount == *amount && event.zec_recipient == *recipient)
+        });
+        Ok(burns)
+    }
+}
+
+/// Decode the burn event from a program log entry
+pub fn burn_event(log: &str) -> Option<BurnEvent> {
+    let encoded = log.strip_prefix("Program data: ")?;
+    let bytes = STANDARD.decode(encoded.trim()).ok()?;
+    let data = bytes.strip_prefix(BurnEvent::DISCRIMINATOR)?;
+    BurnEvent::deserialize(&mut &data[..]).ok()
+}
@@ -0,0 +1,83 @@
+//! Confirmation interfaces of the solana client
+
+use crate::{solana::SolanaClient, TxStatus};
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, hash::Hash, pubkey::Pubkey, signature::Signature,
+};
+use solana_transaction_status_client_types::UiTransactionEncoding;
+use zosh::{
+    client::{AnchorDeserialize, Discriminator},
+    MintEvent,
+};
+
+impl SolanaClient {
+    /// Get the status of a sent transaction
+    ///
+    /// The history is searched as well, so the transactions landed long ago
+    /// are never taken as pending.
+    pub async fn status(&self, signature: &Signature) -> Result<TxStatus> {
+        let statuses = self
+            .tx
+            .program
+            .rpc()
+            .get_signature_statuses_with_history(&[*signature])
+            .await?;
+        let Some(Some(status)) = statuses.value.into_iter().next() else {
+            return Ok(TxStatus::Pending);
+        };
+
+        if let Some(err) = status.err {
+            tracing::warn!("Solana transaction {signature} failed: {err:?}");
+            return Ok(TxStatus::Failed);
+        }
+
+        // NOTE: the confirmations is none once the block is rooted
+        Ok(TxStatus::Confirmed(
+            status.confirmations.map(|c| c as u64).unwrap_or(u64::MAX),
+        ))
+    }
+
+    /// Check if the recent blockhash of a transaction is expired
+    pub async fn expired(&self, blockhash: &Hash) -> Result<bool> {
+        let valid = self
+            .tx
+            .program
+            .rpc()
+            .is_blockhash_valid(blockhash, CommitmentConfig::processed())
+            .await?;
+        Ok(!valid)
+    }
+
+    /// Get the mints of a confirmed transaction
+    pub async fn mints(&self, signature: &Signature) -> Result<Vec<(Pubkey, u64)>> {
+        let tx = self
+            .tx
+            .program
+            .rpc()
+            .get_transaction(signature, UiTransactionEncoding::Json)
+            .await?;
+        let meta = tx.transaction.meta.ok_or(anyhow::anyhow!(
+            "Missing metadata of transaction {signature}"
+        ))?;
+        let logs: Option<Vec<String>> = meta.log_messages.into();
+
+        let mut mints = Vec::new();
+        for log in logs.unwrap_or_default() {
+            let Some(encoded) = log.strip_prefix("Program data: ") else {
+                continue;
+            };
+
+            let bytes = STANDARD.decode(encoded.trim())?;
+            if bytes.len() < 8 || &bytes[..8] != MintEvent::DISCRIMINATOR {
+                continue;
+            }
+
+            let event = MintEvent::deserialize(&mut &bytes[8..])?;
+            mints.extend(event.mints);
+        }
+
+        Ok(mints)
+    }
+}
@@ -1,11 +1,12 @@
 //! Solana sync library
 
-use crate::{ChainFormatEncoder, Config};
+use crate::{ChainFormatEncoder, Config, Metrics};
 use anyhow::Result;
 use solana_pubsub_client::nonblocking::pubsub_client::PubsubClient;
 pub use solana_sdk::{
     pubkey::Pubkey, signature::Signature, signer::keypair::Keypair, transaction::Transaction,
 };
+use std::sync::Arc;
 
 use zcore::{
     ex::{Bridge, BridgeBundle},
@@ -18,7 +19,9 @@ pub use {
     signer::{GroupSigners, SolanaSignerInfo},
 };
 
+mod burn;
 mod cmd;
+mod confirm;
 pub mod dev;
 mod signer;
 mod sub;
@@ -35,6 +38,9 @@ pub struct SolanaClient {
     ///
     /// This is used to sign transactions for development purposes.
     pub dev_mpc: GroupSigners,
+
+    /// The sync metrics
+    pub metrics: Arc<Metrics>,
 }
 
 impl SolanaClient {
@@ -53,6 +59,7 @@ impl SolanaClient {
             tx: solana,
             ws: config.rpc.solana_ws.to_string(),
             dev_mpc,
+            metrics: Default::default(),
         })
     }
 
@@ -78,7 +85,7 @@ impl SolanaClient {
             let recipient = bridge.recipient.solana_address()?;
             mints.push(MintEntry {
                 recipient,
-                amount: bridge.amount,
+                amount: bridge.net(),
             });
             bundle.bridge.push(bridge.clone());
         }
@@ -1,30 +1,34 @@
 //! The subscription of the solana client
 
-use crate::solana::SolanaClient;
+use crate::solana::{burn, SolanaClient};
 use anyhow::Result;
-use base64::{engine::general_purpose::STANDARD, Engine};
 use futures_util::StreamExt;
 use solana_rpc_client_types::{
     config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
     response::RpcLogsResponse,
 };
 use solana_sdk::commitment_config::CommitmentConfig;
+use std::sync::atomic::Ordering;
 use tokio::sync::mpsc;
 use zcore::{
     ex::Bridge,
     registry::{Chain, Coin},
 };
-use zosh::{
-    client::{AnchorDeserialize, Discriminator},
-    BurnEvent,
-};
 
 impl SolanaClient {
     /// Subscribe to the solana client
     ///
     /// Creates a new websocket connection on each retry to handle network changes.
     pub async fn subscribe(&self, tx: mpsc::Sender<Bridge>) {
+        let mut reconnect = false;
         loop {
+            if reconnect {
+                self.metrics
+                    .solana_reconnects
+                    .fetch_add(1, Ordering::Relaxed);
+            }
+            reconnect = true;
+
             // Create a fresh PubsubClient for each connection attempt
             let sub = match self.pubsub().await {
                 Ok(sub) => sub,
@@ -51,8 +55,9 @@ impl SolanaClient {
         sub: solana_pubsub_client::nonblocking::pubsub_client::PubsubClient,
     ) -> Result<()> {
         let filter = RpcTransactionLogsFilter::Mentions(vec![zosh::ID.to_string()]);
+        // NOTE: the burns are verified at the finalized commitment
         let config = RpcTransactionLogsConfig {
-            commitment: Some(CommitmentConfig::confirmed()),
+            commitment: Some(CommitmentConfig::finalized()),
         };
 
         let (mut noti, unsubscribe) = sub.logs_subscribe(filter, config).await?;
@@ -84,28 +89,15 @@ impl SolanaClient {
 
 /// Parse an Anchor event from a Solana program log entry
 async fn handle_event(tx: mpsc::Sender<Bridge>, log: &str, signature: String) -> Result<()> {
-    let data_prefix = "Program data: ";
-    if !log.starts_with(data_prefix) {
+    let Some(burn) = burn::burn_event(log) else {
         return Ok(());
-    }
-
-    let encoded = log.trim_start_matches(data_prefix).trim();
-    let bytes = STANDARD.decode(encoded)?;
-    if bytes.len() < 8 {
-        anyhow::bail!("Invalid log length");
-    }
-
-    // Check if this is a BurnEvent by comparing discriminators
-    if &bytes[..8] != BurnEvent::DISCRIMINATOR {
-        return Ok(());
-    }
+    };
 
-    let data = &mut &bytes[8..];
-    let burn = BurnEvent::deserialize(data)?;
     tx.send(Bridge {
         coin: Coin::Zec,
         recipient: burn.zec_recipient.into(),
         amount: burn.amount,
+        fee: 0,
         txid: bs58::decode(signature).into_vec()?,
         source: Chain::Solana,
         target: Chain::Zcash,
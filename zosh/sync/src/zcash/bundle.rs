@@ -2,6 +2,7 @@
 
 use crate::{zcash::ZcashClient, ChainFormatEncoder};
 use anyhow::Result;
+use std::collections::BTreeSet;
 use zcash_primitives::transaction::{TransactionData, Unauthorized};
 use zcore::{
     ex::{Bridge, BridgeBundle},
@@ -11,16 +12,103 @@ use zcore::{
 impl ZcashClient {
     /// Bundle the bridge transactions
     ///
-    /// TODO: support multiple bridges
+    /// Pays the bridges in one transaction funded by the orchard notes,
+    /// with the outputs of their receiver types. The trailing bridges not
+    /// covered by the spendable notes or the action limit are left out of
+    /// the bundle for the next round.
+    ///
+    /// The recipients of the bridges must be decodable, see
+    /// [`crate::Sync::bundle_zcash_bridges`].
+    ///
+    /// The nullifiers of the orchard actions are carried in the bundle
+    /// data, so the peers can detect the bundles spending the same notes.
     pub async fn bundle(
         &mut self,
         bridges: &[Bridge],
     ) -> Result<(BridgeBundle, TransactionData<Unauthorized>)> {
+        let recipients = bridges
+            .iter()
+            .map(|bridge| Ok((bridge.recipient.zcash_address(&self.network)?, bridge.net())))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (len, utx) = prefix(recipients.len(), |len| self.tx(&recipients[..len]))?;
         let mut bundle = BridgeBundle::new(Chain::Zcash);
-        let bridge = bridges[0].clone();
-        let recipient = bridge.recipient.zcash_address(&self.network)?;
-        let utx = self.tx(recipient, bridge.amount)?;
-        bundle.bridge.push(bridge.clone());
+        bundle.bridge.extend_from_slice(&bridges[..len]);
+        if let Some(orchard) = utx.orchard_bundle() {
+            for action in orchard.actions() {
+                bundle.data.extend(action.nullifier().to_bytes());
+            }
+        }
         Ok((bundle, utx))
     }
+
+    /// Release the notes reserved by a bundle failed to send
+    pub fn release(&self, bundle: &BridgeBundle) -> Result<()> {
+        self.reservations.release(bundle.nullifiers())
+    }
+
+    /// Get the nullifiers of the unspent orchard notes of the wallet
+    pub fn unspent(&self) -> Result<BTreeSet<[u8; 32]>> {
+        let Some(fvk) = self.ufvk.orchard() else {
+            anyhow::bail!("Invalid orchard full viewing key");
+        };
+
+        let (target, _) = self.heights()?;
+        Ok(self
+            .spendable_notes(0, target, &[])?
+            .iter()
+            .map(|note| note.note().nullifier(fvk).to_bytes())
+            .collect())
+    }
+
+    /// Get the nullifiers of the wallet notes spent by the bundle
+    ///
+    /// The bundle data also carries the nullifiers of the dummy spends,
+    /// which are not notes of the wallet.
+    pub fn spends(&self, bundle: &BridgeBundle) -> Result<Vec<[u8; 32]>> {
+        let unspent = self.unspent()?;
+        Ok(bundle
+            .nullifiers()
+            .into_iter()
+            .filter_map(|nullifier| <[u8; 32]>::try_from(nullifier).ok())
+            .filter(|nullifier| unspent.contains(nullifier))
+            .collect())
+    }
+}
+
+/// Build with the longest prefix of the items
+///
+/// Returns the length of the prefix with the built value, or the error
+/// of the shortest prefix.
+fn prefix<T>(len: usize, mut build: impl FnMut(usize) -> Result<T>) -> Result<(usize, T)> {
+    let mut error = anyhow::anyhow!("No bridges to bundle");
+    for len in (1..=len).rev() {
+        match build(len) {
+            Ok(built) => return Ok((len, built)),
+            Err(e) => {
+                tracing::debug!("Failed to bundle {len} zcash bridges: {e}");
+                error = e;
+            }
+        }
+    }
+
+    Err(error)
+}
+
+#[test]
+fn test_prefix() {
+    let mut tried = Vec::new();
+    let built = prefix(8, |len| {
+        tried.push(len);
+        if len > 3 {
+            anyhow::bail!("Too many actions");
+        }
+        Ok(len * 2)
+    });
+    assert_eq!(built.ok(), Some((3, 6)));
+    assert_eq!(tried, vec![8, 7, 6, 5, 4, 3]);
+
+    let error = prefix(2, |len| -> Result<()> { anyhow::bail!("failed {len}") }).unwrap_err();
+    assert_eq!(error.to_string(), "failed 1");
+    assert!(prefix(0, Ok).is_err());
 }
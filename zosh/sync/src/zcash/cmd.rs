@@ -3,15 +3,18 @@
 use crate::config::Config;
 use crate::zcash::{
     self,
-    light::ZcashClient,
+    light::{Deposit, Deposits, ZcashClient},
+    memo::{self, BridgeMemo},
     signer::{GroupSigners, SignerInfo},
-    AddressCodec, UnifiedAddress, UnifiedFullViewingKey,
+    AddressCodec, Recipient, UnifiedFullViewingKey,
 };
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use zcash_client_backend::data_api::wallet::ConfirmationsPolicy;
 use zcash_client_backend::data_api::WalletRead;
 use zcash_client_backend::proto::service::Empty;
+use zcash_protocol::{memo::Memo, TxId};
+use zcore::registry::Chain;
 
 /// Zcash tools
 #[derive(Parser)]
@@ -54,6 +57,26 @@ pub enum Zcash {
         birth: u64,
     },
 
+    /// Encode the bridge memo of a deposit in hex
+    Memo {
+        /// The solana recipient in base58
+        recipient: String,
+
+        /// The zcash address to refund the rejected deposit
+        #[clap(short, long)]
+        refund: Option<String>,
+
+        /// The builder payload in base58
+        #[clap(short, long)]
+        data: Option<String>,
+    },
+
+    /// Inspect the blacklisted deposits
+    Blacklist {
+        #[clap(subcommand)]
+        command: Blacklist,
+    },
+
     Send {
         /// The recipient address
         #[clap(short, long)]
@@ -65,6 +88,26 @@ pub enum Zcash {
     },
 }
 
+/// The blacklist commands
+#[derive(Subcommand)]
+pub enum Blacklist {
+    /// List the blacklisted deposits, the latest first
+    List,
+
+    /// Remove the blacklisted deposits, the spendable notes are scanned again
+    Clear {
+        /// Only the deposits of the transaction, in hex
+        #[clap(short, long)]
+        txid: Option<String>,
+    },
+
+    /// Decode the memos of the blacklisted deposits again
+    Requeue {
+        /// The transaction of the deposits, in hex
+        txid: String,
+    },
+}
+
 impl Zcash {
     /// Run the zcash command
     pub async fn run(&self, config: &Config) -> Result<()> {
@@ -75,6 +118,12 @@ impl Zcash {
             Self::Info => self.info(config),
             Self::Summary => self.summary(&cfg).await,
             Self::Import { ufvk, name, birth } => self.import(&cfg, ufvk, name, *birth).await,
+            Self::Memo {
+                recipient,
+                refund,
+                data,
+            } => self.memo(config, recipient, refund.as_deref(), data.as_deref()),
+            Self::Blacklist { command } => self.blacklist(&cfg, command),
             Self::Send { recipient, amount } => {
                 self.send(&cfg, &config.key.zcash, recipient, *amount).await
             }
@@ -97,6 +146,64 @@ impl Zcash {
         Ok(())
     }
 
+    /// Encode the bridge memo of a deposit
+    fn memo(
+        &self,
+        cfg: &Config,
+        recipient: &str,
+        refund: Option<&str>,
+        data: Option<&str>,
+    ) -> Result<()> {
+        let network: zcash::Network = cfg.network.clone().into();
+        if let Some(refund) = refund {
+            Recipient::decode(&network, refund)
+                .map_err(|e| anyhow::anyhow!("Invalid refund address {refund}: {e}"))?;
+        }
+
+        let memo = BridgeMemo {
+            target: Chain::Solana,
+            recipient: bs58::decode(recipient).into_vec()?,
+            refund: refund.map(Into::into),
+            data: data
+                .map(|data| bs58::decode(data).into_vec())
+                .transpose()?
+                .unwrap_or_default(),
+        };
+
+        // check the memo decodes as the deposits do
+        let bytes = memo.encode()?;
+        memo::recipient(&Memo::try_from(bytes.clone())?)?;
+
+        // the trailing zeros are padded by the wallets
+        let len = bytes
+            .as_slice()
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |last| last + 1);
+        println!("{}", hex::encode(&bytes.as_slice()[..len]));
+        Ok(())
+    }
+
+    /// Inspect the blacklisted deposits
+    fn blacklist(&self, cfg: &zcash::light::Config, command: &Blacklist) -> Result<()> {
+        let deposits = Deposits::for_path(&cfg.cache)?;
+        match command {
+            Blacklist::List => deposits.blacklisted()?.iter().for_each(print_deposit),
+            Blacklist::Clear { txid } => {
+                let txid = txid.as_deref().map(parse_txid).transpose()?;
+                let cleared = deposits.clear(txid.as_ref())?;
+                println!("Cleared {cleared} blacklisted deposits");
+            }
+            Blacklist::Requeue { txid } => {
+                for deposit in deposits.requeue(&parse_txid(txid)?)? {
+                    print_deposit(&deposit);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Get the light client info
     async fn light(&self, cfg: &zcash::light::Config) -> Result<()> {
         let mut light = ZcashClient::new(cfg).await?;
@@ -144,7 +251,7 @@ impl Zcash {
         Ok(())
     }
 
-    /// Send a fund to a orchard address
+    /// Send a fund to a zcash address
     async fn send(
         &self,
         cfg: &zcash::light::Config,
@@ -156,8 +263,7 @@ impl Zcash {
         light
             .dev_send(
                 &postcard::from_bytes(&bs58::decode(group).into_vec()?)?,
-                UnifiedAddress::decode(&light.network, recipient)
-                    .map_err(|e| anyhow::anyhow!(e))?,
+                Recipient::decode(&light.network, recipient)?,
                 (amount * 100_000_000.0).round() as u64,
             )
             .await?;
@@ -165,3 +271,32 @@ impl Zcash {
         Ok(())
     }
 }
+
+/// Parse the zcash txid in the display hex
+fn parse_txid(txid: &str) -> Result<TxId> {
+    let mut bytes = hex::decode(txid)?;
+    bytes.reverse();
+    let Ok(bytes) = <[u8; 32]>::try_from(bytes) else {
+        anyhow::bail!("Invalid zcash txid {txid}");
+    };
+
+    Ok(TxId::from_bytes(bytes))
+}
+
+/// Print a deposit
+fn print_deposit(deposit: &Deposit) {
+    println!(
+        "{}:{} height={} amount={} state={:?} reason={} timestamp={} error={}",
+        deposit.txid,
+        deposit.output,
+        deposit.height,
+        deposit.amount,
+        deposit.state,
+        deposit
+            .reason
+            .map(|reason| reason.to_string())
+            .unwrap_or_default(),
+        deposit.timestamp,
+        deposit.error.as_deref().unwrap_or_default()
+    );
+}
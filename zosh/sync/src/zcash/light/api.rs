@@ -3,13 +3,14 @@
 use crate::zcash::{light::ZcashClient, CONFIRMATIONS};
 use anyhow::Result;
 use orchard::Note;
+use std::sync::atomic::Ordering;
 use zcash_client_backend::{
     data_api::{
         wallet::{ConfirmationsPolicy, TargetHeight},
         Account, AccountBirthday, AccountPurpose, InputSource, MaxSpendMode, TargetValue,
         WalletRead, WalletWrite,
     },
-    proto::service::BlockId,
+    proto::service::{BlockId, ChainSpec},
     sync,
     wallet::ReceivedNote,
 };
@@ -19,7 +20,12 @@ use zcash_protocol::{consensus::BlockHeight, value::Zatoshis, ShieldedProtocol};
 
 impl ZcashClient {
     /// Sync the wallet
+    ///
+    /// `sync::run` truncates the scanned blocks on a reorg, it is detected
+    /// by the hash change of the previously scanned tip, then the deposits
+    /// are rolled back.
     pub async fn sync(&mut self) -> Result<()> {
+        let scanned = self.wallet.block_max_scanned()?;
         sync::run(
             &mut self.client,
             &self.network,
@@ -27,8 +33,40 @@ impl ZcashClient {
             &mut self.wallet,
             100,
         )
-        .await
-        .map_err(Into::into)
+        .await?;
+
+        let Some(scanned) = scanned else {
+            return Ok(());
+        };
+
+        let height = scanned.block_height();
+        let hash = self
+            .wallet
+            .block_metadata(height)?
+            .map(|meta| meta.block_hash());
+        if reorged(scanned.block_hash(), hash) {
+            tracing::warn!("Zcash reorg detected at height {}", u32::from(height));
+            self.metrics.zcash_reorgs.fetch_add(1, Ordering::Relaxed);
+            self.rollback_deposits(height)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the scanned height of the wallet and the height of the chain tip
+    pub async fn progress(&mut self) -> Result<(u32, u32)> {
+        let scanned = self
+            .wallet
+            .block_max_scanned()?
+            .map(|meta| u32::from(meta.block_height()))
+            .unwrap_or_default();
+        let tip = self
+            .client
+            .get_latest_block(ChainSpec {})
+            .await?
+            .into_inner()
+            .height;
+        Ok((scanned, tip as u32))
     }
 
     /// Import a unified full viewing key
@@ -107,3 +145,17 @@ impl ZcashClient {
         Ok(notes.orchard().to_vec())
     }
 }
+
+/// Check if the previously scanned tip is reorged
+///
+/// The tip is reorged once its hash changes, or it is truncated.
+fn reorged<H: PartialEq>(scanned: H, hash: Option<H>) -> bool {
+    hash != Some(scanned)
+}
+
+#[test]
+fn test_reorged() {
+    assert!(!reorged([1; 32], Some([1; 32])));
+    assert!(reorged([1; 32], Some([2; 32])));
+    assert!(reorged([1; 32], None));
+}
@@ -22,4 +22,7 @@ pub struct Config {
 
     /// The unified full viewing key to import
     pub ufvk: UnifiedFullViewingKey,
+
+    /// The confirmations required for emitting the deposits
+    pub depth: u64,
 }
@@ -0,0 +1,167 @@
+//! Confirmation interfaces of the zcash light client
+
+use crate::{
+    zcash::{Recipient, ZcashClient},
+    TxStatus,
+};
+use anyhow::Result;
+use orchard::keys::Scope;
+use sapling_crypto::{keys::OutgoingViewingKey, note_encryption::try_sapling_output_recovery};
+use zcash_client_backend::proto::service::{ChainSpec, TxFilter};
+use zcash_primitives::transaction::{components::sapling::zip212_enforcement, Transaction};
+use zcash_protocol::{
+    consensus::{BlockHeight, BranchId},
+    memo::{Memo, MemoBytes},
+    TxId,
+};
+
+impl ZcashClient {
+    /// Get the height of the chain tip
+    pub async fn tip(&mut self) -> Result<u64> {
+        let block = self
+            .client
+            .get_latest_block(ChainSpec {})
+            .await?
+            .into_inner();
+        Ok(block.height)
+    }
+
+    /// Get the status of a sent transaction
+    ///
+    /// NOTE: lightwalletd returns height 0 for the transactions in the
+    /// mempool and `u64::MAX` for the ones not in the main chain, both of
+    /// them and the unknown transactions are not mined. The other errors
+    /// of lightwalletd are returned.
+    pub async fn status(&mut self, txid: TxId) -> Result<TxStatus> {
+        let rawtx = match self
+            .client
+            .get_transaction(TxFilter {
+                block: None,
+                index: 0,
+                hash: txid.as_ref().to_vec(),
+            })
+            .await
+        {
+            Ok(rawtx) => rawtx.into_inner(),
+            Err(status) if missing(&status) => return Ok(TxStatus::Missing),
+            Err(status) => return Err(status.into()),
+        };
+
+        match rawtx.height {
+            0 => return Ok(TxStatus::Pending),
+            u64::MAX => return Ok(TxStatus::Missing),
+            _ => {}
+        }
+
+        let tip = self.tip().await?;
+        Ok(TxStatus::Confirmed(
+            tip.saturating_sub(rawtx.height).saturating_add(1),
+        ))
+    }
+
+    /// Get the outputs sent by the bridge in a mined transaction
+    ///
+    /// The shielded outputs are recovered with the outgoing viewing key of
+    /// the bridge, the transparent outputs are public.
+    pub async fn sent(&mut self, txid: TxId) -> Result<Vec<(Recipient, u64)>> {
+        let tx = self.transaction(txid).await?;
+        let ovk = self
+            .ufvk
+            .orchard()
+            .ok_or(anyhow::anyhow!("Failed to get orchard full viewing key"))?
+            .to_ovk(Scope::External);
+        let bundle = tx
+            .orchard_bundle()
+            .ok_or(anyhow::anyhow!("Failed to get orchard bundle"))?;
+
+        let mut outputs = Vec::new();
+        for idx in 0..bundle.actions().len() {
+            if let Some((note, address, _memo)) = bundle.recover_output_with_ovk(idx, &ovk) {
+                outputs.push((Recipient::Orchard(address), note.value().inner()));
+            }
+        }
+
+        if let Some(bundle) = tx.sapling_bundle() {
+            let ovk = OutgoingViewingKey(*ovk.as_ref());
+            let zip212 = zip212_enforcement(&self.network, tx.expiry_height());
+            for output in bundle.shielded_outputs() {
+                if let Some((note, address, _memo)) =
+                    try_sapling_output_recovery(&ovk, output, zip212)
+                {
+                    outputs.push((Recipient::Sapling(address), note.value().inner()));
+                }
+            }
+        }
+
+        if let Some(bundle) = tx.transparent_bundle() {
+            for output in &bundle.vout {
+                if let Some(address) = output.recipient_address() {
+                    outputs.push((Recipient::Transparent(address), output.value().into_u64()));
+                }
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    /// Get the orchard notes received by the bridge in a mined transaction
+    ///
+    /// The outputs are decrypted with the incoming viewing key of the bridge,
+    /// returns the values with the memos.
+    pub async fn received(&mut self, txid: TxId) -> Result<Vec<(u64, Memo)>> {
+        let tx = self.transaction(txid).await?;
+        let ivk = self
+            .ufvk
+            .orchard()
+            .ok_or(anyhow::anyhow!("Failed to get orchard full viewing key"))?
+            .to_ivk(Scope::External);
+        let bundle = tx
+            .orchard_bundle()
+            .ok_or(anyhow::anyhow!("Failed to get orchard bundle"))?;
+
+        let mut outputs = Vec::new();
+        for idx in 0..bundle.actions().len() {
+            if let Some((note, _address, memo)) = bundle.decrypt_output_with_key(idx, &ivk) {
+                let memo = Memo::try_from(MemoBytes::from_bytes(&memo)?)?;
+                outputs.push((note.value().inner(), memo));
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    /// Get a mined transaction
+    async fn transaction(&mut self, txid: TxId) -> Result<Transaction> {
+        let rawtx = self
+            .client
+            .get_transaction(TxFilter {
+                block: None,
+                index: 0,
+                hash: txid.as_ref().to_vec(),
+            })
+            .await?
+            .into_inner();
+        let height = BlockHeight::from_u32(u32::try_from(rawtx.height)?);
+        Ok(Transaction::read(
+            rawtx.data.as_slice(),
+            BranchId::for_height(&self.network, height),
+        )?)
+    }
+}
+
+/// Check if lightwalletd doesn't know the transaction
+fn missing(status: &tonic::Status) -> bool {
+    status.code() == tonic::Code::NotFound
+        || status
+            .message()
+            .contains("No such mempool or blockchain transaction")
+}
+
+#[test]
+fn test_missing() {
+    assert!(missing(&tonic::Status::not_found("transaction not found")));
+    assert!(missing(&tonic::Status::unknown(
+        "-5: No such mempool or blockchain transaction. Use gettransaction for wallet transactions."
+    )));
+    assert!(!missing(&tonic::Status::unavailable("connection refused")));
+}
@@ -0,0 +1,553 @@
+//! The deposit index of the zcash collector
+
+use crate::zcash::memo;
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::{
+    fmt::{self, Display},
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use zcash_protocol::{
+    memo::{Memo, MemoBytes},
+    TxId,
+};
+
+/// The columns of the deposits
+const COLUMNS: &str =
+    "txid, output, height, amount, memo, recipient, reason, error, timestamp, state";
+
+/// The processing state of a deposit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepositState {
+    /// Waiting for the deposit depth
+    Pending,
+
+    /// Emitted to the relay
+    Emitted,
+
+    /// Blacklisted with the reason
+    Invalid,
+
+    /// Emitted, but not mined at its height anymore after a reorg
+    Reorged,
+}
+
+impl DepositState {
+    /// Get the code of the state in the database
+    fn code(&self) -> u8 {
+        match self {
+            Self::Pending => 0,
+            Self::Emitted => 1,
+            Self::Invalid => 2,
+            Self::Reorged => 3,
+        }
+    }
+
+    /// Get the state from the code in the database
+    fn from_code(code: u8) -> Result<Self> {
+        Ok(match code {
+            0 => Self::Pending,
+            1 => Self::Emitted,
+            2 => Self::Invalid,
+            3 => Self::Reorged,
+            _ => anyhow::bail!("Invalid deposit state {code}"),
+        })
+    }
+}
+
+/// The reason of blacklisting a deposit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    /// The memo is not a bridge memo
+    BadMemo,
+
+    /// The recipient has a wrong length
+    WrongLength,
+
+    /// The mined height of the note is not found
+    MissingHeight,
+
+    /// The target chain is not supported
+    UnsupportedTarget,
+}
+
+impl Reason {
+    /// Get the code of the reason in the database
+    fn code(&self) -> u8 {
+        match self {
+            Self::BadMemo => 1,
+            Self::WrongLength => 2,
+            Self::MissingHeight => 3,
+            Self::UnsupportedTarget => 4,
+        }
+    }
+
+    /// Get the reason from the code in the database
+    fn from_code(code: u8) -> Result<Self> {
+        Ok(match code {
+            1 => Self::BadMemo,
+            2 => Self::WrongLength,
+            3 => Self::MissingHeight,
+            4 => Self::UnsupportedTarget,
+            _ => anyhow::bail!("Invalid blacklist reason {code}"),
+        })
+    }
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadMemo => write!(f, "bad_memo"),
+            Self::WrongLength => write!(f, "wrong_length"),
+            Self::MissingHeight => write!(f, "missing_height"),
+            Self::UnsupportedTarget => write!(f, "unsupported_target"),
+        }
+    }
+}
+
+/// A deposit scanned by the collector
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deposit {
+    /// The transaction id of the deposit
+    pub txid: TxId,
+
+    /// The orchard output index of the note
+    pub output: u32,
+
+    /// The mined height of the transaction
+    pub height: u32,
+
+    /// The value of the note
+    pub amount: u64,
+
+    /// The decrypted memo bytes
+    pub memo: Vec<u8>,
+
+    /// The recipient decoded from the memo
+    pub recipient: Option<Vec<u8>>,
+
+    /// The reason of the blacklisting
+    pub reason: Option<Reason>,
+
+    /// The error message of the blacklisting
+    pub error: Option<String>,
+
+    /// The unix timestamp of the latest state change
+    pub timestamp: u64,
+
+    /// The processing state
+    pub state: DepositState,
+}
+
+impl Deposit {
+    /// Decode the recipient from the memo, blacklisting the invalid ones
+    pub fn decode(&mut self) -> Result<()> {
+        let memo = Memo::try_from(MemoBytes::from_bytes(&self.memo)?)?;
+        match memo::check(&memo) {
+            Ok(recipient) => {
+                self.recipient = Some(recipient);
+                self.reason = None;
+                self.error = None;
+                self.state = DepositState::Pending;
+            }
+            Err((reason, e)) => self.blacklist(reason, e.to_string()),
+        }
+
+        self.timestamp = now();
+        Ok(())
+    }
+
+    /// Blacklist the deposit with the reason
+    pub fn blacklist(&mut self, reason: Reason, error: String) {
+        self.recipient = None;
+        self.reason = Some(reason);
+        self.error = Some(error);
+        self.timestamp = now();
+        self.state = DepositState::Invalid;
+    }
+
+    /// Decode the deposit from a database row
+    fn from_row(row: &Row) -> Result<Self> {
+        let txid: Vec<u8> = row.get(0)?;
+        let Ok(txid) = <[u8; 32]>::try_from(txid) else {
+            anyhow::bail!("Invalid deposit txid");
+        };
+
+        Ok(Self {
+            txid: TxId::from_bytes(txid),
+            output: row.get(1)?,
+            height: row.get(2)?,
+            amount: row.get::<_, i64>(3)? as u64,
+            memo: row.get(4)?,
+            recipient: row.get(5)?,
+            reason: row
+                .get::<_, Option<u8>>(6)?
+                .map(Reason::from_code)
+                .transpose()?,
+            error: row.get(7)?,
+            timestamp: row.get::<_, i64>(8)? as u64,
+            state: DepositState::from_code(row.get(9)?)?,
+        })
+    }
+}
+
+/// The deposits scanned by the collector, keyed by the note
+///
+/// The memo of a note is fetched and decoded once when it is scanned,
+/// the collector only emits the pending deposits.
+pub struct Deposits {
+    /// The database connection
+    conn: Mutex<Connection>,
+}
+
+impl Deposits {
+    /// Open the deposits from a path
+    pub fn for_path(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path.as_ref())?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS deposits (
+                txid BLOB NOT NULL,
+                output INTEGER NOT NULL,
+                height INTEGER NOT NULL,
+                amount INTEGER NOT NULL,
+                memo BLOB NOT NULL,
+                recipient BLOB,
+                reason INTEGER,
+                error TEXT,
+                timestamp INTEGER NOT NULL,
+                state INTEGER NOT NULL,
+                PRIMARY KEY (txid, output)
+            )",
+            [],
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Get the processing state of the note, `None` if not scanned
+    pub fn state(&self, txid: &TxId, output: u32) -> Result<Option<DepositState>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.query_row(
+            "SELECT state FROM deposits WHERE txid = ?1 AND output = ?2",
+            params![txid.as_ref(), output],
+            |row| row.get::<_, u8>(0),
+        )
+        .optional()?
+        .map(DepositState::from_code)
+        .transpose()
+    }
+
+    /// Check if an emitted deposit of the transaction is reorged
+    pub fn reorged(&self, txid: &TxId) -> Result<bool> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let reorged = conn
+            .query_row(
+                "SELECT 1 FROM deposits WHERE txid = ?1 AND state = ?2",
+                params![txid.as_ref(), DepositState::Reorged.code()],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(reorged.is_some())
+    }
+
+    /// Insert a scanned deposit, the existing one is kept
+    pub fn insert(&self, deposit: &Deposit) -> Result<()> {
+        self.write("INSERT OR IGNORE", deposit)
+    }
+
+    /// Insert or replace a deposit
+    pub fn replace(&self, deposit: &Deposit) -> Result<()> {
+        self.write("INSERT OR REPLACE", deposit)
+    }
+
+    /// Write the deposit with the insert statement
+    fn write(&self, insert: &str, deposit: &Deposit) -> Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            &format!(
+                "{insert} INTO deposits ({COLUMNS})
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"
+            ),
+            params![
+                deposit.txid.as_ref(),
+                deposit.output,
+                deposit.height,
+                deposit.amount as i64,
+                deposit.memo,
+                deposit.recipient,
+                deposit.reason.map(|reason| reason.code()),
+                deposit.error,
+                deposit.timestamp as i64,
+                deposit.state.code(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Get the cached memo of the note
+    pub fn memo(&self, txid: &TxId, output: u32) -> Result<Option<Vec<u8>>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(conn
+            .query_row(
+                "SELECT memo FROM deposits WHERE txid = ?1 AND output = ?2",
+                params![txid.as_ref(), output],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Get the pending deposits mined at or below the height
+    pub fn pending(&self, height: u32) -> Result<Vec<Deposit>> {
+        self.query(
+            "state = ?1 AND height <= ?2 ORDER BY height",
+            params![DepositState::Pending.code(), height],
+        )
+    }
+
+    /// Get the pending and emitted deposits mined at or above the height
+    pub fn since(&self, height: u32) -> Result<Vec<Deposit>> {
+        self.query(
+            "state IN (?1, ?2) AND height >= ?3",
+            params![
+                DepositState::Pending.code(),
+                DepositState::Emitted.code(),
+                height
+            ],
+        )
+    }
+
+    /// Get the blacklisted deposits, the latest first
+    pub fn blacklisted(&self) -> Result<Vec<Deposit>> {
+        self.query(
+            "state = ?1 ORDER BY timestamp DESC",
+            params![DepositState::Invalid.code()],
+        )
+    }
+
+    /// Query the deposits with the condition
+    fn query(&self, condition: &str, params: impl rusqlite::Params) -> Result<Vec<Deposit>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt =
+            conn.prepare(&format!("SELECT {COLUMNS} FROM deposits WHERE {condition}"))?;
+        let mut rows = stmt.query(params)?;
+        let mut deposits = Vec::new();
+        while let Some(row) = rows.next()? {
+            deposits.push(Deposit::from_row(row)?);
+        }
+        Ok(deposits)
+    }
+
+    /// Update the processing state of the deposit
+    pub fn set_state(&self, txid: &TxId, output: u32, state: DepositState) -> Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "UPDATE deposits SET state = ?1, timestamp = ?2 WHERE txid = ?3 AND output = ?4",
+            params![state.code(), now() as i64, txid.as_ref(), output],
+        )?;
+        Ok(())
+    }
+
+    /// Remove the deposit of the note
+    pub fn remove(&self, txid: &TxId, output: u32) -> Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "DELETE FROM deposits WHERE txid = ?1 AND output = ?2",
+            params![txid.as_ref(), output],
+        )?;
+        Ok(())
+    }
+
+    /// Remove the blacklisted deposits, all of them without the txid
+    ///
+    /// The removed notes are scanned again if they are still spendable.
+    pub fn clear(&self, txid: Option<&TxId>) -> Result<usize> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let state = DepositState::Invalid.code();
+        Ok(match txid {
+            Some(txid) => conn.execute(
+                "DELETE FROM deposits WHERE state = ?1 AND txid = ?2",
+                params![state, txid.as_ref()],
+            )?,
+            None => conn.execute("DELETE FROM deposits WHERE state = ?1", params![state])?,
+        })
+    }
+
+    /// Requeue the blacklisted deposits of the transaction
+    ///
+    /// The cached memos are decoded again, the valid deposits are pending
+    /// for emitting. The deposits without the memo are removed to be
+    /// scanned again. Returns the decoded deposits.
+    pub fn requeue(&self, txid: &TxId) -> Result<Vec<Deposit>> {
+        let mut requeued = Vec::new();
+        for mut deposit in self.blacklisted()? {
+            if deposit.txid != *txid {
+                continue;
+            }
+
+            if deposit.reason == Some(Reason::MissingHeight) {
+                self.remove(txid, deposit.output)?;
+                continue;
+            }
+
+            deposit.decode()?;
+            self.replace(&deposit)?;
+            requeued.push(deposit);
+        }
+        Ok(requeued)
+    }
+
+    /// Roll back the deposits since the height not mined at their heights
+    ///
+    /// The current mined heights of the transactions are queried with
+    /// `mined`, the pending deposits are removed and the emitted ones are
+    /// flagged as reorged. Returns the rolled back deposits.
+    pub fn rollback(
+        &self,
+        since: u32,
+        mined: impl Fn(&TxId) -> Result<Option<u32>>,
+    ) -> Result<Vec<Deposit>> {
+        let mut reorged = Vec::new();
+        for deposit in self.since(since)? {
+            let height = mined(&deposit.txid)?;
+            if height == Some(deposit.height) {
+                continue;
+            }
+
+            tracing::warn!(
+                "Deposit {}:{} at height {} is reorged, now at {height:?}",
+                deposit.txid,
+                deposit.output,
+                deposit.height
+            );
+            match deposit.state {
+                DepositState::Pending => self.remove(&deposit.txid, deposit.output)?,
+                _ => self.set_state(&deposit.txid, deposit.output, DepositState::Reorged)?,
+            }
+            reorged.push(deposit);
+        }
+
+        Ok(reorged)
+    }
+
+    /// Count the blacklisted deposits
+    pub fn invalid(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM deposits WHERE state = ?1",
+            params![DepositState::Invalid.code()],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+}
+
+/// Get the current unix timestamp
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_deposits() -> Result<()> {
+    let deposits = Deposits::for_path(":memory:")?;
+    let deposit = Deposit {
+        txid: TxId::from_bytes([1; 32]),
+        output: 0,
+        height: 100,
+        amount: 100_000_000,
+        memo: vec![0xf5; 512],
+        recipient: Some(vec![2; 32]),
+        reason: None,
+        error: None,
+        timestamp: 1,
+        state: DepositState::Pending,
+    };
+    let mut invalid = Deposit {
+        output: 1,
+        ..deposit.clone()
+    };
+    invalid.blacklist(Reason::BadMemo, "Invalid bridge memo magic".into());
+    deposits.insert(&deposit)?;
+    deposits.insert(&invalid)?;
+
+    assert_eq!(
+        deposits.state(&deposit.txid, 1)?,
+        Some(DepositState::Invalid)
+    );
+    assert_eq!(deposits.state(&deposit.txid, 2)?, None);
+    assert_eq!(deposits.memo(&deposit.txid, 0)?, Some(vec![0xf5; 512]));
+    assert_eq!(deposits.invalid()?, 1);
+    assert!(deposits.pending(99)?.is_empty());
+    assert_eq!(deposits.pending(100)?, vec![deposit.clone()]);
+
+    assert_eq!(deposits.blacklisted()?, vec![invalid]);
+
+    deposits.set_state(&deposit.txid, 0, DepositState::Emitted)?;
+    assert!(deposits.pending(100)?.is_empty());
+    assert_eq!(deposits.since(100)?.len(), 1);
+    assert!(!deposits.reorged(&deposit.txid)?);
+
+    deposits.set_state(&deposit.txid, 0, DepositState::Reorged)?;
+    assert!(deposits.since(100)?.is_empty());
+    assert!(deposits.reorged(&deposit.txid)?);
+    assert_eq!(deposits.clear(Some(&deposit.txid))?, 1);
+    assert_eq!(deposits.invalid()?, 0);
+    Ok(())
+}
+
+#[test]
+fn test_rollback() -> Result<()> {
+    let deposits = Deposits::for_path(":memory:")?;
+    let deposit = |txid: u8, height: u32, state: DepositState| Deposit {
+        txid: TxId::from_bytes([txid; 32]),
+        output: 0,
+        height,
+        amount: 100_000_000,
+        memo: vec![0xf5; 512],
+        recipient: Some(vec![2; 32]),
+        reason: None,
+        error: None,
+        timestamp: 1,
+        state,
+    };
+
+    // the deposits below the window, still mined, moved and dropped
+    let old = deposit(1, 50, DepositState::Emitted);
+    let mined = deposit(2, 100, DepositState::Emitted);
+    let moved = deposit(3, 101, DepositState::Pending);
+    let dropped = deposit(4, 102, DepositState::Emitted);
+    for deposit in [&old, &mined, &moved, &dropped] {
+        deposits.insert(deposit)?;
+    }
+
+    let reorged = deposits.rollback(100, |txid| {
+        Ok(match txid.as_ref()[0] {
+            2 => Some(100),
+            3 => Some(103),
+            _ => None,
+        })
+    })?;
+    assert_eq!(reorged.len(), 2);
+    assert_eq!(deposits.state(&old.txid, 0)?, Some(DepositState::Emitted));
+    assert_eq!(deposits.state(&mined.txid, 0)?, Some(DepositState::Emitted));
+    assert_eq!(deposits.state(&moved.txid, 0)?, None);
+    assert_eq!(
+        deposits.state(&dropped.txid, 0)?,
+        Some(DepositState::Reorged)
+    );
+    assert!(deposits.reorged(&dropped.txid)?);
+
+    // the failed queries keep the deposits
+    deposits.insert(&moved)?;
+    assert!(deposits
+        .rollback(100, |_| anyhow::bail!("database is locked"))
+        .is_err());
+    assert_eq!(deposits.state(&moved.txid, 0)?, Some(DepositState::Pending));
+    Ok(())
+}
@@ -1,21 +1,29 @@
 //! Zcash light client
 
+use crate::Metrics;
 use anyhow::Result;
 use cache::BlockDb;
 pub use config::Config;
 use rusqlite::Connection;
-use std::{fs, path::Path};
+use std::{fs, path::Path, sync::Arc};
 use tonic::transport::{Channel, ClientTlsConfig};
 use zcash_client_backend::{
     data_api::WalletRead, proto::service::compact_tx_streamer_client::CompactTxStreamerClient,
 };
-use zcash_client_sqlite::{util::SystemClock, wallet, ReceivedNoteId, WalletDb};
+use zcash_client_sqlite::{util::SystemClock, wallet, WalletDb};
 use zcash_keys::keys::UnifiedFullViewingKey;
 use zcash_protocol::consensus::Network;
+pub use {
+    deposit::{Deposit, DepositState, Deposits, Reason},
+    reserve::Reservations,
+};
 
 mod api;
 mod cache;
 mod config;
+mod confirm;
+mod deposit;
+mod reserve;
 mod sub;
 mod tx;
 
@@ -36,10 +44,17 @@ pub struct ZcashClient {
     /// The unified full viewing key of the light client
     pub ufvk: UnifiedFullViewingKey,
 
-    /// The blacklist of the light client
-    ///
-    /// NOTE: should not use this in production, only for development.
-    pub blacklist: Vec<ReceivedNoteId>,
+    /// The notes reserved by the unconfirmed transactions
+    pub reservations: Reservations,
+
+    /// The deposits scanned by the collector
+    pub deposits: Deposits,
+
+    /// The confirmations required for emitting the deposits
+    pub depth: u64,
+
+    /// The sync metrics
+    pub metrics: Arc<Metrics>,
 }
 
 impl ZcashClient {
@@ -51,6 +66,8 @@ impl ZcashClient {
 
         let cache = Path::new(&config.cache);
         let block = BlockDb::for_path(cache)?;
+        let reservations = Reservations::for_path(cache)?;
+        let deposits = Deposits::for_path(cache)?;
 
         // create the wallet database
         let mut wallet = WalletDb::for_path(
@@ -75,7 +92,10 @@ impl ZcashClient {
             client,
             network: config.network,
             ufvk: config.ufvk.clone(),
-            blacklist: vec![],
+            reservations,
+            deposits,
+            depth: config.depth,
+            metrics: Default::default(),
         };
 
         // import the account if it doesn't exist
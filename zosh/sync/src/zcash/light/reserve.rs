@@ -0,0 +1,92 @@
+//! Reservations of the notes spent by the unconfirmed transactions
+
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use std::{collections::BTreeSet, path::Path, sync::Mutex};
+
+/// The reservations of the spent notes
+///
+/// A note is reserved by its nullifier once it is selected for a
+/// transaction, until the transaction expires. The notes spent by the
+/// mined transactions never show up as spendable again.
+pub struct Reservations {
+    /// The database connection
+    conn: Mutex<Connection>,
+}
+
+impl Reservations {
+    /// Open the reservations from a path
+    pub fn for_path(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path.as_ref())?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS reservations (
+                nullifier BLOB PRIMARY KEY,
+                expiry INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Reserve the nullifiers until the expiry height
+    pub fn reserve(&self, nullifiers: &[[u8; 32]], expiry: u32) -> Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn
+            .prepare("INSERT OR REPLACE INTO reservations (nullifier, expiry) VALUES (?1, ?2)")?;
+        for nullifier in nullifiers {
+            stmt.execute(params![&nullifier[..], expiry])?;
+        }
+        Ok(())
+    }
+
+    /// Release the nullifiers
+    pub fn release<'n>(&self, nullifiers: impl IntoIterator<Item = &'n [u8]>) -> Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare("DELETE FROM reservations WHERE nullifier = ?1")?;
+        for nullifier in nullifiers {
+            stmt.execute(params![nullifier])?;
+        }
+        Ok(())
+    }
+
+    /// Get the reserved nullifiers at the target height
+    ///
+    /// The reservations of the transactions can't be mined at the height
+    /// anymore are pruned.
+    pub fn active(&self, target: u32) -> Result<BTreeSet<[u8; 32]>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "DELETE FROM reservations WHERE expiry < ?1",
+            params![target],
+        )?;
+
+        let mut stmt = conn.prepare("SELECT nullifier FROM reservations")?;
+        let mut rows = stmt.query([])?;
+        let mut active = BTreeSet::new();
+        while let Some(row) = rows.next()? {
+            let nullifier: Vec<u8> = row.get(0)?;
+            let Ok(nullifier) = nullifier.try_into() else {
+                anyhow::bail!("Invalid reserved nullifier");
+            };
+            active.insert(nullifier);
+        }
+        Ok(active)
+    }
+}
+
+#[test]
+fn test_reservations() -> Result<()> {
+    let reservations = Reservations::for_path(":memory:")?;
+    reservations.reserve(&[[1; 32], [2; 32]], 100)?;
+    reservations.reserve(&[[3; 32]], 120)?;
+    reservations.release([&[2; 32][..]])?;
+    assert_eq!(
+        reservations.active(100)?,
+        BTreeSet::from([[1; 32], [3; 32]])
+    );
+    assert_eq!(reservations.active(101)?, BTreeSet::from([[3; 32]]));
+    Ok(())
+}
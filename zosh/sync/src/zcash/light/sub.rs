@@ -1,11 +1,16 @@
 //! The subscription of the zcash light client
 
-use crate::zcash::ZcashClient;
+use crate::zcash::{
+    light::{Deposit, DepositState, Reason},
+    memo::BridgeMemo,
+    ZcashClient,
+};
 use anyhow::Result;
 use orchard::keys::Scope;
-use std::time::Duration;
+use std::{sync::atomic::Ordering, time::Duration};
 use tokio::{sync::mpsc, time};
 use zcash_client_backend::{
+    data_api::{wallet::TargetHeight, WalletRead},
     fees::orchard::InputView,
     proto::service::{BlockId, TxFilter},
 };
@@ -23,105 +28,136 @@ use zcore::{
 /// The block time of zcash in seconds
 pub const ZCASH_BLOCK_TIME: u64 = 75;
 
+/// The blocks checked for the reorged deposits below the reorged height
+const REORG_WINDOW: u32 = 100;
+
 impl ZcashClient {
-    /// Subscribe to the zcash light client
+    /// Poll the zcash light client for the deposits
+    ///
+    /// The new notes are indexed into the deposits with their memos once,
+    /// the pending deposits reached the deposit depth are returned and
+    /// marked as emitted. The relay layer still deduplicates with
+    /// `parity.exists(&bridge.txid)`.
     ///
     /// FIXME: write new query of the walletdb to fetch the
     /// latest transactions efficiently.
-    pub async fn subscribe(&mut self, tx: mpsc::Sender<Bridge>) {
-        loop {
-            if let Err(e) = self.subscribe_inner(tx.clone()).await {
-                tracing::error!(
-                    "Zcash light client subscription error:{e:?}, retrying in 5 seconds"
-                );
-                tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+    pub async fn poll(&mut self) -> Result<Vec<Bridge>> {
+        self.sync().await?;
+        let (target, _anchor) = self.heights()?;
+        tracing::trace!("zcash light synced to height {}", u32::from(target));
+        match self.progress().await {
+            Ok((scanned, tip)) => {
+                self.metrics.zcash_height.store(scanned, Ordering::Relaxed);
+                self.metrics.zcash_tip.store(tip, Ordering::Relaxed);
             }
+            Err(e) => tracing::warn!("Failed to get the zcash sync progress: {e:?}"),
         }
-    }
 
-    /// Subscribe to the zcash light client
-    ///
-    /// Note: Deduplication is handled by the relay layer using the database,
-    /// so we simply send all spendable notes. This avoids memory issues from
-    /// tracking processed txids and ensures no notes are missed.
-    pub async fn subscribe_inner(&mut self, tx: mpsc::Sender<Bridge>) -> Result<()> {
-        loop {
-            self.sync().await?;
-            let Ok((target, _anchor)) = self.heights() else {
-                tracing::error!(
-                    "Failed to get max height and hash, retrying in {} seconds",
-                    ZCASH_BLOCK_TIME
-                );
-                time::sleep(Duration::from_secs(ZCASH_BLOCK_TIME)).await;
+        self.index_deposits(target).await?;
+
+        // emit the deposits reached the deposit depth
+        let mut bridges = Vec::new();
+        let height = u32::from(target).saturating_sub(self.depth as u32);
+        for deposit in self.deposits.pending(height)? {
+            let Some(recipient) = deposit.recipient else {
                 continue;
             };
-            tracing::trace!("zcash light synced to height {}", u32::from(target));
 
-            // Get the spendable notes and send them all.
-            // The relay layer deduplicates using parity.exists(&bridge.txid).
-            let notes = self.spendable_notes(0, target, &self.blacklist)?;
-            for note in notes.into_iter() {
-                let txid = note.txid();
-                let Some(mined_height) = note.mined_height() else {
-                    tracing::warn!("Note mined height is not found for note of {}", &txid);
-                    self.blacklist.push(*note.internal_note_id());
-                    continue;
-                };
+            self.deposits
+                .set_state(&deposit.txid, deposit.output, DepositState::Emitted)?;
+            bridges.push(Bridge {
+                coin: Coin::Zec,
+                recipient,
+                amount: deposit.amount,
+                fee: 0,
+                txid: deposit.txid.as_ref().to_vec(),
+                source: Chain::Zcash,
+                target: Chain::Solana,
+            });
+        }
 
-                let Ok(Memo::Text(text)) = self
-                    .fetch_memo(mined_height, *txid, note.output_index() as u32)
-                    .await
-                    .inspect_err(|e| {
-                        tracing::warn!("Failed to fetch memo for note of {}: {:?}", &txid, e);
-                    })
-                else {
-                    // TODO: raise a refund event to the node.
-                    self.blacklist.push(*note.internal_note_id());
-                    continue;
-                };
+        self.metrics
+            .blacklisted
+            .store(self.deposits.invalid()?, Ordering::Relaxed);
+        Ok(bridges)
+    }
 
-                // split the memo into parts
-                let parts = text.trim().split(':').collect::<Vec<&str>>();
-                if parts.is_empty() {
-                    self.blacklist.push(*note.internal_note_id());
-                    continue;
-                }
+    /// Index the new spendable notes into the deposits
+    ///
+    /// The memos failed to fetch are retried in the next round, the change
+    /// notes of the internal scope are not deposits.
+    async fn index_deposits(&mut self, target: TargetHeight) -> Result<()> {
+        let notes = self.spendable_notes(0, target, &[])?;
+        for note in notes.into_iter() {
+            if note.spending_key_scope() == Scope::Internal {
+                continue;
+            }
 
-                // NOTE: support solana only
-                //
-                // TODO: check the address length is valid, if not, introduce
-                // a refund transaction to the node, since we'll recheck it
-                // on sending the transaction, not doing it here for now.
-                //
-                // TODO: for non-bridge memo, we should blacklist them.
-                let Ok(recipient) = bs58::decode(parts[0]).into_vec() else {
-                    self.blacklist.push(*note.internal_note_id());
-                    continue;
-                };
+            let txid = *note.txid();
+            let output = note.output_index() as u32;
+            match self.deposits.state(&txid, output)? {
+                None | Some(DepositState::Reorged) => {}
+                Some(_) => continue,
+            }
 
-                // NOTE: Invalid recipient address, skip it.
-                if recipient.len() != 32 {
-                    self.blacklist.push(*note.internal_note_id());
-                    continue;
-                }
+            let mut deposit = Deposit {
+                txid,
+                output,
+                height: 0,
+                amount: note.value().into_u64(),
+                memo: Vec::new(),
+                recipient: None,
+                reason: None,
+                error: None,
+                timestamp: 0,
+                state: DepositState::Pending,
+            };
+
+            let Some(mined_height) = note.mined_height() else {
+                tracing::warn!("Note mined height is not found for note of {}", &txid);
+                deposit.blacklist(Reason::MissingHeight, "Missing mined height".into());
+                self.deposits.replace(&deposit)?;
+                continue;
+            };
 
-                // NOTE: we support solana address only here, for the bytes
-                // after 32, they will be used for the builders to enhance
-                // the user experience.
-                tx.send(Bridge {
-                    coin: Coin::Zec,
-                    recipient,
-                    amount: note.value().into_u64(),
-                    txid: txid.as_ref().to_vec(),
-                    source: Chain::Zcash,
-                    target: Chain::Solana,
+            let Ok(memo) = self
+                .fetch_memo(mined_height, txid, output)
+                .await
+                .inspect_err(|e| {
+                    tracing::warn!("Failed to fetch memo for note of {}: {:?}", &txid, e);
                 })
-                .await?;
-            }
+            else {
+                continue;
+            };
 
-            // The block time of zcash is 75 secs, using 10 secs is fine here.
-            time::sleep(Duration::from_secs(10)).await;
+            // NOTE: support solana only
+            //
+            // TODO: introduce a refund transaction to the node for the
+            // invalid recipients.
+            deposit.height = u32::from(mined_height);
+            deposit.memo = memo.encode().as_slice().to_vec();
+            deposit.decode()?;
+            if let (Some(reason), Some(error)) = (deposit.reason, &deposit.error) {
+                tracing::warn!("Blacklisted note of {} ({reason}): {error}", &txid);
+            }
+            self.deposits.replace(&deposit)?;
         }
+
+        Ok(())
+    }
+
+    /// Roll back the deposits not mined at their heights anymore
+    ///
+    /// The pending deposits are removed to be indexed again once they are
+    /// mined, the emitted ones are flagged as reorged for the bundler to
+    /// drop their bridge requests.
+    pub fn rollback_deposits(&mut self, height: BlockHeight) -> Result<()> {
+        let since = u32::from(height).saturating_sub(REORG_WINDOW);
+        let wallet = &self.wallet;
+        self.deposits.rollback(since, |txid| {
+            Ok(wallet.get_tx_height(*txid)?.map(u32::from))
+        })?;
+        Ok(())
     }
 
     /// Subscribe to the zcash light client for builder data
@@ -146,45 +182,45 @@ impl ZcashClient {
                 let txid = note.txid();
                 let Some(mined_height) = note.mined_height() else {
                     tracing::warn!("Note mined height is not found for note of {}", &txid);
-                    self.blacklist.push(*note.internal_note_id());
                     continue;
                 };
 
-                let Ok(Memo::Text(text)) = self
+                let Ok(memo) = self
                     .fetch_memo(mined_height, *txid, note.output_index() as u32)
                     .await
                     .inspect_err(|e| {
                         tracing::warn!("Failed to fetch memo for note of {}: {:?}", &txid, e);
                     })
                 else {
-                    self.blacklist.push(*note.internal_note_id());
                     continue;
                 };
 
-                let parts = text.trim().split(':').collect::<Vec<&str>>();
-                if parts.len() != 2 {
-                    self.blacklist.push(*note.internal_note_id());
+                let Ok(BridgeMemo { data, .. }) = BridgeMemo::decode(&memo) else {
                     continue;
-                }
+                };
 
-                let Ok(data) = bs58::decode(parts[1]).into_vec() else {
-                    self.blacklist.push(*note.internal_note_id());
+                if data.is_empty() {
                     continue;
-                };
+                }
 
                 tx.send((data, *txid)).await?;
             }
         }
     }
 
-    /// TODO: introduce memory cache for this or flush it to
-    /// the walletdb.
+    /// Fetch the memo of a received note
+    ///
+    /// The memos of the indexed deposits are read from the cache.
     async fn fetch_memo(
         &mut self,
         height: BlockHeight,
         txid: TxId,
         output_index: u32,
     ) -> Result<Memo> {
+        if let Some(memo) = self.deposits.memo(&txid, output_index)? {
+            return Ok(Memo::try_from(MemoBytes::from_bytes(&memo)?)?);
+        }
+
         let block = self
             .client
             .get_block(BlockId {
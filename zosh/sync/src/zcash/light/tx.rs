@@ -1,28 +1,38 @@
 //! Transaction related interfaces
 
-use crate::zcash::{light::ZcashClient, signer::GroupSigners};
+use crate::zcash::{light::ZcashClient, signer::GroupSigners, Recipient, SAPLING_PROVER};
 use anyhow::Result;
 use incrementalmerkletree::MerklePath;
 use orchard::{
     builder::{self, BundleType, OutputInfo, SpendInfo},
     bundle::Flags,
-    keys::Scope,
+    keys::{FullViewingKey, OutgoingViewingKey, Scope},
     tree::MerkleHashOrchard,
     value::NoteValue,
     Anchor, Note,
 };
+use sapling_crypto::{
+    builder::{self as sapling_builder, Builder as SaplingBuilder},
+    keys::OutgoingViewingKey as SaplingOutgoingViewingKey,
+    Bundle as SaplingBundle, PaymentAddress,
+};
 use zcash_client_backend::{
-    data_api::WalletCommitmentTrees, fees::orchard::InputView, proto::service::RawTransaction,
+    data_api::{wallet::TargetHeight, WalletCommitmentTrees},
+    fees::orchard::InputView,
+    proto::service::RawTransaction,
     wallet::ReceivedNote,
 };
 use zcash_client_sqlite::ReceivedNoteId;
-use zcash_keys::address::UnifiedAddress;
-use zcash_primitives::transaction::{TransactionData, TxVersion, Unauthorized};
+use zcash_primitives::transaction::{
+    components::sapling::zip212_enforcement, TransactionData, TxVersion, Unauthorized,
+};
+use zcash_proofs::prover::LocalTxProver;
 use zcash_protocol::{
     consensus::{BlockHeight, BranchId},
-    value::ZatBalance,
+    value::{ZatBalance, Zatoshis},
     TxId,
 };
+use zcash_transparent::{address::TransparentAddress, builder::TransparentBuilder};
 
 /// ZIP-317 base fee (for up to 2 logical actions)
 const ZIP317_BASE_FEE: u64 = 10_000;
@@ -33,14 +43,31 @@ const ZIP317_MARGINAL_FEE: u64 = 5_000;
 /// ZIP-317 grace actions (no marginal fee for first 2 actions)
 const ZIP317_GRACE_ACTIONS: usize = 2;
 
+/// The maximum orchard actions of a transaction
+///
+/// The proving time grows linearly with the actions, this keeps the
+/// proof of a bundle within a few seconds.
+pub const MAX_ACTIONS: usize = 16;
+
 /// The memo for a bridged transaction
 const BRIDGE_MEMO: [u8; 31] = *b"Bridged from solana via zosh.io";
 
 /// The memo for a change output
 const CHANGE_MEMO: [u8; 512] = [0; 512];
 
-/// Calculate ZIP-317 fee for a given number of actions
-/// In Orchard, num_actions = max(num_spends, num_outputs)
+/// The minimum outputs of a sapling bundle, a single output is padded
+const MIN_SAPLING_OUTPUTS: usize = 2;
+
+/// The unproven sapling bundle of an unauthorized transaction
+type UnauthorizedSapling = SaplingBundle<
+    sapling_builder::InProgress<sapling_builder::Proven, sapling_builder::Unsigned>,
+    ZatBalance,
+>;
+
+/// Calculate ZIP-317 fee for a given number of logical actions
+///
+/// In Orchard, num_actions = max(num_spends, num_outputs), the sapling
+/// outputs and the P2PKH or P2SH outputs count one logical action each.
 fn calculate_zip317_fee(num_actions: usize) -> u64 {
     if num_actions <= ZIP317_GRACE_ACTIONS {
         ZIP317_BASE_FEE
@@ -49,6 +76,51 @@ fn calculate_zip317_fee(num_actions: usize) -> u64 {
     }
 }
 
+/// The recipient outputs split by their pools
+#[derive(Debug, Default, PartialEq)]
+struct Outputs {
+    /// The orchard outputs
+    orchard: Vec<(orchard::Address, u64)>,
+
+    /// The sapling outputs
+    sapling: Vec<(PaymentAddress, u64)>,
+
+    /// The transparent outputs
+    transparent: Vec<(TransparentAddress, u64)>,
+}
+
+impl Outputs {
+    /// Split the recipients by their pools
+    fn new(recipients: &[(Recipient, u64)]) -> Self {
+        let mut outputs = Self::default();
+        for (recipient, amount) in recipients {
+            match recipient {
+                Recipient::Orchard(address) => outputs.orchard.push((*address, *amount)),
+                Recipient::Sapling(address) => outputs.sapling.push((*address, *amount)),
+                Recipient::Transparent(address) => outputs.transparent.push((*address, *amount)),
+            }
+        }
+        outputs
+    }
+
+    /// The proven actions spending the notes with a change output
+    ///
+    /// The orchard actions are max(spends, outputs), the sapling outputs
+    /// are padded to the minimum of the sapling bundle.
+    fn proven(&self, spends: usize) -> usize {
+        let sapling = match self.sapling.len() {
+            0 => 0,
+            len => len.max(MIN_SAPLING_OUTPUTS),
+        };
+        spends.max(self.orchard.len() + 1) + sapling
+    }
+
+    /// The logical actions of ZIP-317 spending the notes with a change output
+    fn logical(&self, spends: usize) -> usize {
+        self.proven(spends) + self.transparent.len()
+    }
+}
+
 impl ZcashClient {
     /// Sign and send a transaction for development purposes
     pub async fn dev_sign_and_send(
@@ -81,14 +153,14 @@ impl ZcashClient {
         Ok(txid)
     }
 
-    /// Send a fund to a orchard address for development purposes
+    /// Send a fund to a zcash address for development purposes
     pub async fn dev_send(
         &mut self,
         signer: &GroupSigners,
-        recipient: UnifiedAddress,
+        recipient: Recipient,
         amount: u64,
     ) -> Result<()> {
-        let utx = self.tx(recipient, amount)?;
+        let utx = self.tx(&[(recipient, amount)])?;
         let tx = signer.sign_tx(utx)?.freeze()?;
         let txid = tx.txid();
         let mut data = Vec::new();
@@ -115,75 +187,97 @@ impl ZcashClient {
         Ok(())
     }
 
-    /// Send a fund to a orchard address for development purposes
-    /// The `amount` parameter is the total to spend (including fee).
-    /// The recipient will receive `amount - fee`.
-    pub fn tx(
-        &mut self,
-        recipient: UnifiedAddress,
-        amount: u64,
-    ) -> Result<TransactionData<Unauthorized>> {
-        let Some(recipient) = recipient.orchard() else {
-            return Err(anyhow::anyhow!("Invalid orchard address"));
-        };
+    /// Build a transaction paying the zcash recipients
+    ///
+    /// The recipients receive exactly their amounts, the orchard ones in
+    /// the orchard bundle, the sapling ones in a sapling bundle without
+    /// spends, and the transparent ones in transparent outputs. All of them
+    /// are funded by the orchard notes of the bridge. The ZIP-317 network
+    /// fee is paid by the bridge on top of them, which is covered by the
+    /// bridge fees charged from the gross amounts of the requests.
+    ///
+    /// The spent notes are reserved until the transaction expires, so the
+    /// concurrent transactions never select them again.
+    pub fn tx(&mut self, recipients: &[(Recipient, u64)]) -> Result<TransactionData<Unauthorized>> {
+        if recipients.is_empty() {
+            return Err(anyhow::anyhow!("No recipients to pay"));
+        }
 
         let Some(fvk) = self.ufvk.orchard().cloned() else {
             return Err(anyhow::anyhow!("Invalid orchard full viewing key"));
         };
 
-        // 1. Select notes to cover the total amount (which includes fee)
+        // 1. Prepare the recipient outputs
+        //
+        // NOTE: the shielded recipient outputs are encrypted to the outgoing
+        // viewing key of the bridge, so validators can recover them to verify
+        // the receipts.
+        let ovk = fvk.to_ovk(Scope::External);
+        let pools = Outputs::new(recipients);
+        let mut recipient_memo = [0; 512];
+        recipient_memo[..31].copy_from_slice(&BRIDGE_MEMO);
+        let mut outputs = pools
+            .orchard
+            .iter()
+            .map(|(address, amount)| {
+                OutputInfo::new(
+                    Some(ovk.clone()),
+                    *address,
+                    NoteValue::from_raw(*amount),
+                    recipient_memo,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // 2. Select notes to cover the amount with the minimum fee, we may
+        // need more notes once we know the actual number of actions.
+        //
+        // In orchard, the actions are max(spends, outputs), assume we'll have
+        // change to calculate the fee conservatively. The sapling outputs are
+        // padded to the minimum of the sapling bundle.
+        let amount: u64 = recipients.iter().map(|(_, amount)| amount).sum();
         let (target_height, anchor_height) = self.heights()?;
-        let notes = self.spendable_notes(amount, target_height, &[])?;
-        if notes.is_empty() {
-            return Err(anyhow::anyhow!("No spendable notes found"));
-        }
+        let reserved = self.reserved(target_height, &fvk)?;
+        let mut fee = calculate_zip317_fee(pools.logical(0));
+        let mut notes = self.spendable_notes(amount + fee, target_height, &reserved)?;
+        loop {
+            if notes.is_empty() {
+                return Err(anyhow::anyhow!("No spendable notes found"));
+            }
 
-        let total_note_value: u64 = notes.iter().map(|note| note.value().into_u64()).sum();
-        let num_spends = notes.len();
-
-        // 2. Calculate the actual number of outputs and actions
-        // We'll have at least 1 output (recipient), possibly 2 if there's change
-        // For now, assume we'll have change to calculate fee conservatively
-        let num_outputs_with_change = 2;
-        let num_actions = num_spends.max(num_outputs_with_change);
-        let fee = calculate_zip317_fee(num_actions);
-        if amount < fee {
-            return Err(anyhow::anyhow!(
-                "Amount {} is less than required fee {}",
-                amount,
-                fee
-            ));
+            let proven = pools.proven(notes.len());
+            if proven > MAX_ACTIONS {
+                return Err(anyhow::anyhow!(
+                    "Too many actions: {proven} exceeds the limit {MAX_ACTIONS}"
+                ));
+            }
+
+            let required = calculate_zip317_fee(pools.logical(notes.len()));
+            if required <= fee {
+                break;
+            }
+
+            fee = required;
+            notes = self.spendable_notes(amount + fee, target_height, &reserved)?;
         }
 
-        // Calculate the delivery amount (what recipient actually receives)
-        let delivery_amount = amount
-            .checked_sub(fee)
-            .ok_or_else(|| anyhow::anyhow!("Amount too small to cover fee"))?;
-        if total_note_value < amount {
+        let total_note_value: u64 = notes.iter().map(|note| note.value().into_u64()).sum();
+        let total = amount + fee;
+        if total_note_value < total {
             return Err(anyhow::anyhow!(
                 "Insufficient funds: have {}, need {} (delivery: {}, fee: {})",
                 total_note_value,
+                total,
                 amount,
-                delivery_amount,
                 fee
             ));
         }
 
-        // 3. Prepare outputs: recipient output + change output (if any)
-        let change = total_note_value - amount;
-        let mut outputs = Vec::new();
-        let mut recipient_memo = [0; 512];
-        recipient_memo[..31].copy_from_slice(&BRIDGE_MEMO);
-        outputs.push(OutputInfo::new(
-            None,
-            *recipient,
-            NoteValue::from_raw(delivery_amount),
-            recipient_memo,
-        ));
-
-        // If there's change, send it back to our own address
+        // If there's change, send it back to our internal address, so it
+        // is never indexed as a deposit
+        let change = total_note_value - total;
         if change > 0 {
-            let change_address = fvk.address_at(0u64, Scope::External);
+            let change_address = fvk.address_at(0u64, Scope::Internal);
             outputs.push(OutputInfo::new(
                 None,
                 change_address,
@@ -221,21 +315,106 @@ impl ZcashClient {
             return Err(anyhow::anyhow!("Failed to create bundle"));
         };
 
+        // 5. make the sapling and transparent bundles of the transaction
+        let sapling = self.sapling_bundle(
+            BlockHeight::from(target_height),
+            &ovk,
+            &pools.sapling,
+            recipient_memo,
+        )?;
+        let transparent = transparent_bundle(&pools.transparent)?;
+
         // Determine the correct branch ID based on the target height
         let branch_id = BranchId::for_height(&self.network, BlockHeight::from(target_height));
         let expiry_height = BlockHeight::from(target_height) + 20;
+        let nullifiers = notes
+            .iter()
+            .map(|note| note.note().nullifier(&fvk).to_bytes())
+            .collect::<Vec<_>>();
+        self.reservations
+            .reserve(&nullifiers, u32::from(expiry_height))?;
         Ok(TransactionData::<Unauthorized>::from_parts(
             TxVersion::suggested_for_branch(branch_id),
             branch_id,
             0,
             expiry_height,
+            transparent,
             None,
-            None,
-            None,
+            sapling,
             Some(bundle),
         ))
     }
 
+    /// Build the sapling bundle paying the sapling recipients
+    ///
+    /// The bundle has no spends, its outputs are proven here as the proofs
+    /// are not signed, the binding signature is applied by the signers.
+    fn sapling_bundle(
+        &self,
+        height: BlockHeight,
+        ovk: &OutgoingViewingKey,
+        recipients: &[(PaymentAddress, u64)],
+        memo: [u8; 512],
+    ) -> Result<Option<UnauthorizedSapling>> {
+        if recipients.is_empty() {
+            return Ok(None);
+        }
+
+        let Some(prover) = SAPLING_PROVER.as_ref() else {
+            return Err(anyhow::anyhow!(
+                "Missing sapling parameters for sapling recipients"
+            ));
+        };
+
+        // NOTE: the sapling outputs share the outgoing viewing key of the
+        // orchard bundle, so validators recover them with the same key.
+        let ovk = SaplingOutgoingViewingKey(*ovk.as_ref());
+        let mut builder = SaplingBuilder::new(
+            zip212_enforcement(&self.network, height),
+            sapling_builder::BundleType::DEFAULT,
+            sapling_crypto::Anchor::empty_tree(),
+        );
+        for (recipient, amount) in recipients {
+            builder
+                .add_output(
+                    Some(ovk),
+                    *recipient,
+                    sapling_crypto::value::NoteValue::from_raw(*amount),
+                    memo,
+                )
+                .map_err(|e| anyhow::anyhow!("Failed to add sapling output: {e:?}"))?;
+        }
+
+        let Some((bundle, _meta)) = builder
+            .build::<LocalTxProver, LocalTxProver, _, ZatBalance>(&[], rand_core::OsRng)
+            .map_err(|e| anyhow::anyhow!("Failed to build sapling bundle: {e:?}"))?
+        else {
+            return Err(anyhow::anyhow!("Failed to create sapling bundle"));
+        };
+
+        Ok(Some(bundle.create_proofs(
+            prover,
+            prover,
+            rand_core::OsRng,
+            (),
+        )))
+    }
+
+    /// Get the spendable notes reserved by the unconfirmed transactions
+    fn reserved(&self, target: TargetHeight, fvk: &FullViewingKey) -> Result<Vec<ReceivedNoteId>> {
+        let reserved = self.reservations.active(u32::from(target))?;
+        if reserved.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(self
+            .spendable_notes(0, target, &[])?
+            .into_iter()
+            .filter(|note| reserved.contains(&note.note().nullifier(fvk).to_bytes()))
+            .map(|note| *note.internal_note_id())
+            .collect())
+    }
+
     /// Get the merkle path for the notes at the given height
     fn merkle_path(
         &mut self,
@@ -269,3 +448,51 @@ impl ZcashClient {
             })
     }
 }
+
+/// Build the transparent bundle paying the transparent recipients
+fn transparent_bundle(
+    recipients: &[(TransparentAddress, u64)],
+) -> Result<Option<zcash_transparent::bundle::Bundle<zcash_transparent::builder::Unauthorized>>> {
+    let mut builder = TransparentBuilder::empty();
+    for (recipient, amount) in recipients {
+        let value = Zatoshis::from_u64(*amount)
+            .map_err(|e| anyhow::anyhow!("Invalid transparent amount {amount}: {e:?}"))?;
+        builder
+            .add_output(recipient, value)
+            .map_err(|e| anyhow::anyhow!("Failed to add transparent output: {e:?}"))?;
+    }
+
+    Ok(builder.build())
+}
+
+#[test]
+fn test_outputs() -> Result<()> {
+    let sk =
+        Option::<orchard::keys::SpendingKey>::from(orchard::keys::SpendingKey::from_bytes([7; 32]))
+            .ok_or_else(|| anyhow::anyhow!("Invalid orchard spending key"))?;
+    let orchard = FullViewingKey::from(&sk).address_at(0u64, Scope::External);
+    let (_, sapling) =
+        sapling_crypto::zip32::ExtendedSpendingKey::master(&[7; 32]).default_address();
+    let transparent = TransparentAddress::PublicKeyHash([7; 20]);
+
+    let outputs = Outputs::new(&[
+        (Recipient::Orchard(orchard), 1),
+        (Recipient::Sapling(sapling), 2),
+        (Recipient::Transparent(transparent), 3),
+        (Recipient::Orchard(orchard), 4),
+    ]);
+    assert_eq!(outputs.orchard, vec![(orchard, 1), (orchard, 4)]);
+    assert_eq!(outputs.sapling, vec![(sapling, 2)]);
+    assert_eq!(outputs.transparent, vec![(transparent, 3)]);
+
+    // two orchard outputs with the change, one sapling output padded to two
+    assert_eq!(outputs.proven(1), 5);
+    assert_eq!(outputs.proven(4), 6);
+    assert_eq!(outputs.logical(4), 7);
+
+    // the transparent only recipients still have the change action
+    let outputs = Outputs::new(&[(Recipient::Transparent(transparent), 3)]);
+    assert_eq!(outputs.proven(0), 1);
+    assert_eq!(outputs.logical(2), 3);
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! The memo of the bridge deposits
+
+use crate::zcash::light::Reason;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use zcash_protocol::memo::{Memo, MemoBytes};
+use zcore::registry::Chain;
+
+/// The ZIP-302 leading byte of the arbitrary data memos
+pub const ARBITRARY_MEMO: u8 = 0xf5;
+
+/// The magic of the bridge memos
+pub const MEMO_MAGIC: [u8; 4] = *b"zosh";
+
+/// The version of the bridge memo format
+pub const MEMO_VERSION: u8 = 1;
+
+/// The length of the memo checksum
+const CHECKSUM_LENGTH: usize = 4;
+
+/// The bridge memo of a deposit
+///
+/// Encoded as a ZIP-302 arbitrary data memo:
+/// `0xf5 || MAGIC || VERSION || postcard(memo) || checksum`, where the
+/// checksum is the first 4 bytes of the blake3 hash of the bytes between
+/// `0xf5` and itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BridgeMemo {
+    /// The target chain of the deposit
+    pub target: Chain,
+
+    /// The recipient on the target chain
+    pub recipient: Vec<u8>,
+
+    /// The zcash address to refund the rejected deposit
+    pub refund: Option<String>,
+
+    /// The payload for the builders
+    pub data: Vec<u8>,
+}
+
+impl BridgeMemo {
+    /// Encode the memo
+    pub fn encode(&self) -> Result<MemoBytes> {
+        let mut bytes = vec![ARBITRARY_MEMO];
+        bytes.extend(MEMO_MAGIC);
+        bytes.push(MEMO_VERSION);
+        bytes.extend(postcard::to_allocvec(self)?);
+
+        let checksum = crypto::blake3(&bytes[1..]);
+        bytes.extend(&checksum[..CHECKSUM_LENGTH]);
+        MemoBytes::from_bytes(&bytes).map_err(|e| anyhow::anyhow!("Invalid bridge memo: {e}"))
+    }
+
+    /// Decode the memo of a deposit
+    ///
+    /// The text memo `<recipient>[:<data>]` with the base58 solana address
+    /// and the base58 builder payload is accepted as well.
+    pub fn decode(memo: &Memo) -> Result<Self> {
+        match memo {
+            Memo::Arbitrary(data) => Self::decode_binary(&data[..]),
+            Memo::Text(text) => Self::decode_text(text),
+            _ => anyhow::bail!("Unsupported memo, expected a bridge memo"),
+        }
+    }
+
+    /// Decode the binary memo from the arbitrary data
+    fn decode_binary(data: &[u8]) -> Result<Self> {
+        let Some(payload) = data.strip_prefix(&MEMO_MAGIC[..]) else {
+            anyhow::bail!("Invalid bridge memo magic");
+        };
+
+        let Some((&version, encoded)) = payload.split_first() else {
+            anyhow::bail!("Missing bridge memo version");
+        };
+
+        if version != MEMO_VERSION {
+            anyhow::bail!("Unsupported bridge memo version {version}");
+        }
+
+        let (memo, rest) = postcard::take_from_bytes::<Self>(encoded)?;
+        let end = data.len() - rest.len();
+        let Some(checksum) = rest.get(..CHECKSUM_LENGTH) else {
+            anyhow::bail!("Missing bridge memo checksum");
+        };
+
+        if checksum != &crypto::blake3(&data[..end])[..CHECKSUM_LENGTH] {
+            anyhow::bail!("Invalid bridge memo checksum");
+        }
+
+        Ok(memo)
+    }
+
+    /// Decode the legacy text memo
+    fn decode_text(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let (address, data) = match text.split_once(':') {
+            Some((address, data)) => (address, Some(data)),
+            None => (text, None),
+        };
+
+        let recipient = bs58::decode(address)
+            .into_vec()
+            .map_err(|e| anyhow::anyhow!("Invalid solana recipient {address}: {e}"))?;
+        let data = data
+            .map(|data| bs58::decode(data).into_vec())
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid builder data: {e}"))?
+            .unwrap_or_default();
+        Ok(Self {
+            target: Chain::Solana,
+            recipient,
+            refund: None,
+            data,
+        })
+    }
+}
+
+/// Decode the solana recipient from the memo of a deposit
+pub fn recipient(memo: &Memo) -> Result<Vec<u8>> {
+    check(memo).map_err(|(_, e)| e)
+}
+
+/// Decode the solana recipient, or the reason to blacklist the deposit
+pub fn check(memo: &Memo) -> Result<Vec<u8>, (Reason, anyhow::Error)> {
+    let memo = BridgeMemo::decode(memo).map_err(|e| (Reason::BadMemo, e))?;
+    if memo.target != Chain::Solana {
+        return Err((
+            Reason::UnsupportedTarget,
+            anyhow::anyhow!("Unsupported target chain {:?}", memo.target),
+        ));
+    }
+
+    if memo.recipient.len() != 32 {
+        return Err((
+            Reason::WrongLength,
+            anyhow::anyhow!(
+                "Invalid solana recipient {}: expected 32 bytes",
+                bs58::encode(&memo.recipient).into_string()
+            ),
+        ));
+    }
+
+    Ok(memo.recipient)
+}
+
+#[test]
+fn test_memo_recipient() -> Result<()> {
+    use std::str::FromStr;
+
+    let address = bs58::encode([1; 32]).into_string();
+    let data = bs58::encode(b"builder").into_string();
+    for text in [address.clone(), format!(" {address}:{data} ")] {
+        assert_eq!(recipient(&Memo::from_str(&text)?)?, vec![1; 32]);
+    }
+
+    assert!(recipient(&Memo::Empty).is_err());
+    assert!(recipient(&Memo::from_str("not-an-address")?).is_err());
+    assert!(recipient(&Memo::from_str(&bs58::encode([1; 16]).into_string())?).is_err());
+    assert!(matches!(
+        check(&Memo::from_str(&bs58::encode([1; 16]).into_string())?),
+        Err((Reason::WrongLength, _))
+    ));
+    assert!(matches!(
+        check(&Memo::from_str("not-an-address")?),
+        Err((Reason::BadMemo, _))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_bridge_memo() -> Result<()> {
+    let memo = BridgeMemo {
+        target: Chain::Solana,
+        recipient: vec![1; 32],
+        refund: Some("u1refund".into()),
+        data: b"builder".to_vec(),
+    };
+
+    let bytes = memo.encode()?;
+    assert_eq!(bytes.as_slice()[0], ARBITRARY_MEMO);
+    assert_eq!(BridgeMemo::decode(&Memo::try_from(bytes.clone())?)?, memo);
+    assert_eq!(recipient(&Memo::try_from(bytes.clone())?)?, vec![1; 32]);
+
+    // tampered recipient
+    let mut tampered = bytes.as_slice().to_vec();
+    tampered[8] ^= 1;
+    let tampered = Memo::try_from(MemoBytes::from_bytes(&tampered)?)?;
+    assert!(BridgeMemo::decode(&tampered).is_err());
+
+    // unknown version
+    let mut version = bytes.as_slice().to_vec();
+    version[5] = MEMO_VERSION + 1;
+    let version = Memo::try_from(MemoBytes::from_bytes(&version)?)?;
+    assert!(BridgeMemo::decode(&version).is_err());
+    Ok(())
+}
@@ -3,10 +3,12 @@
 use orchard::circuit::ProvingKey;
 use std::sync::LazyLock;
 use zcash_client_backend::data_api::wallet::ConfirmationsPolicy;
+use zcash_proofs::prover::LocalTxProver;
 pub use {
     cmd::Zcash,
-    light::{Config, ZcashClient},
+    light::{Config, Deposit, DepositState, Deposits, Reason, ZcashClient},
     orchard::Address,
+    recipient::Recipient,
     signer::{GroupSigners, ShareSigner, SignerInfo},
     zcash_keys::{address::UnifiedAddress, encoding::AddressCodec, keys::UnifiedFullViewingKey},
     zcash_protocol::{
@@ -18,6 +20,8 @@ pub use {
 mod bundle;
 mod cmd;
 mod light;
+pub mod memo;
+mod recipient;
 mod signer;
 
 /// The confirmations policy for the zcash light client
@@ -25,3 +29,10 @@ pub static CONFIRMATIONS: ConfirmationsPolicy = ConfirmationsPolicy::MIN;
 
 /// The proving key for the orchard transactions
 pub static PROVING_KEY: LazyLock<ProvingKey> = LazyLock::new(ProvingKey::build);
+
+/// The prover of the sapling outputs
+///
+/// Loaded from the default location of the sapling parameters, the
+/// transactions paying sapling recipients fail without them.
+pub static SAPLING_PROVER: LazyLock<Option<LocalTxProver>> =
+    LazyLock::new(LocalTxProver::with_default_location);
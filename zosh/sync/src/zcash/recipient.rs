@@ -0,0 +1,88 @@
+//! Recipients of the zcash withdrawals
+
+use anyhow::Result;
+use sapling_crypto::PaymentAddress;
+use zcash_keys::address::{Address as ZcashAddress, UnifiedAddress};
+use zcash_protocol::consensus::Network;
+use zcash_transparent::address::TransparentAddress;
+
+/// The receiver paid by a withdrawal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recipient {
+    /// An orchard receiver
+    Orchard(orchard::Address),
+
+    /// A sapling receiver
+    Sapling(PaymentAddress),
+
+    /// A transparent receiver, also the one of a TEX address
+    Transparent(TransparentAddress),
+}
+
+impl Recipient {
+    /// Decode the recipient from an encoded zcash address
+    ///
+    /// The unified addresses are paid to their most private receiver,
+    /// orchard first, then sapling, then transparent.
+    ///
+    /// NOTE: the TEX addresses are paid to their P2PKH receivers from the
+    /// shielded notes of the bridge, the transparent-source-only transfers
+    /// of ZIP 320 are not supported yet.
+    pub fn decode(network: &Network, address: &str) -> Result<Self> {
+        let Some(decoded) = ZcashAddress::decode(network, address) else {
+            anyhow::bail!("Invalid zcash address {address}");
+        };
+
+        Ok(match decoded {
+            ZcashAddress::Unified(ua) => {
+                if let Some(orchard) = ua.orchard() {
+                    Self::Orchard(*orchard)
+                } else if let Some(sapling) = ua.sapling() {
+                    Self::Sapling(*sapling)
+                } else if let Some(transparent) = ua.transparent() {
+                    Self::Transparent(*transparent)
+                } else {
+                    anyhow::bail!("No supported receiver in unified address {address}");
+                }
+            }
+            ZcashAddress::Sapling(sapling) => Self::Sapling(sapling),
+            ZcashAddress::Transparent(transparent) => Self::Transparent(transparent),
+            ZcashAddress::Tex(pkh) => Self::Transparent(TransparentAddress::PublicKeyHash(pkh)),
+        })
+    }
+
+    /// Encode the recipient as a zcash address
+    pub fn encode(&self, network: &Network) -> String {
+        match self {
+            Self::Orchard(orchard) => {
+                match UnifiedAddress::from_receivers(Some(*orchard), None, None) {
+                    Some(ua) => ZcashAddress::Unified(ua).encode(network),
+                    None => format!("{orchard:?}"),
+                }
+            }
+            Self::Sapling(sapling) => ZcashAddress::Sapling(*sapling).encode(network),
+            Self::Transparent(transparent) => {
+                ZcashAddress::Transparent(*transparent).encode(network)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_decode_transparent() -> Result<()> {
+    let network = Network::TestNetwork;
+    let pkh = TransparentAddress::PublicKeyHash([7; 20]);
+    let taddr = ZcashAddress::Transparent(pkh).encode(&network);
+    assert_eq!(
+        Recipient::decode(&network, &taddr)?,
+        Recipient::Transparent(pkh)
+    );
+
+    let tex = ZcashAddress::Tex([7; 20]).encode(&network);
+    assert_eq!(
+        Recipient::decode(&network, &tex)?,
+        Recipient::Transparent(pkh)
+    );
+    assert!(Recipient::decode(&network, "t1invalid").is_err());
+    Ok(())
+}
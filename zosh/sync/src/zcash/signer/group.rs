@@ -19,6 +19,7 @@ use zcash_primitives::transaction::{
     txid::TxIdDigester,
     Authorized, TransactionData, Unauthorized,
 };
+use zcash_transparent::bundle as transparent;
 
 /// DEV_ONLY: Zcash group signers
 #[derive(Serialize, Deserialize)]
@@ -88,6 +89,10 @@ impl GroupSigners {
     }
 
     /// Sign a transaction with the group of signers
+    ///
+    /// The sapling and transparent bundles only carry the outputs of the
+    /// recipients, they are authorized with the binding signature of the
+    /// sapling outputs and no transparent signatures.
     pub fn sign_tx(
         &self,
         utx: TransactionData<Unauthorized>,
@@ -135,14 +140,26 @@ impl GroupSigners {
             .finalize()
             .map_err(|_e| anyhow::anyhow!("Failed to finalize"))?;
 
+        let sapling = utx
+            .sapling_bundle()
+            .cloned()
+            .map(|bundle| bundle.apply_signatures(rand_core::OsRng, *sighash.as_ref(), &[]))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Failed to sign sapling bundle: {e:?}"))?;
+        let transparent = utx.transparent_bundle().map(|bundle| transparent::Bundle {
+            vin: Vec::new(),
+            vout: bundle.vout.clone(),
+            authorization: transparent::Authorized,
+        });
+
         Ok(TransactionData::<Authorized>::from_parts(
             utx.version(),
             utx.consensus_branch_id(),
             0,
             utx.expiry_height(),
+            transparent,
             None,
-            None,
-            None,
+            sapling,
             Some(proven),
         ))
     }